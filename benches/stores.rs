@@ -0,0 +1,117 @@
+// How the stores do as the knowledge grows - every store gets the same terms and the same
+// operations on them. The timings are printed, nothing is asserted about them
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use its_logical::knowledge::model::fat_term::{parse_fat_term, FatTerm};
+use its_logical::knowledge::store::{
+    embedded::EmbeddedTerms, persistent::Terms, Delete, Get, Keys, Load, Put, Transact,
+};
+
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+// of each kind - spread evenly over the terms
+const OPERATIONS: usize = 100;
+
+fn term(idx: usize, fact: &str) -> FatTerm {
+    parse_fat_term(&format!(
+        "% -term{idx} the term number {idx}
+% @arg X the x
+% @arg Y the y
+% @see
+term{idx}({fact},b).
+term{idx}(X,Y):-other(X),other(Y).
+"
+    ))
+    .expect("the benchmarked terms parse")
+    .1
+}
+
+fn knowledge_dir(store: &str, size: usize) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "its_logical_bench_{}_{}_{}",
+        store,
+        size,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).expect("the temp dir is writable");
+    path
+}
+
+fn per_operation(start: Instant) -> Duration {
+    start.elapsed() / OPERATIONS as u32
+}
+
+fn bench<S>(store: &str, size: usize, export: impl Fn(&S))
+where
+    S: Load<Store = S> + Get + Put + Delete + Keys + Transact,
+{
+    let path = knowledge_dir(store, size);
+    let step = size / OPERATIONS;
+
+    let start = Instant::now();
+    let mut terms = S::load(&path).expect("a new store loads");
+    let mut transaction = terms.begin();
+    for idx in 0..size {
+        transaction.put(&format!("term{}/2", idx), term(idx, "a"));
+    }
+    terms.commit(transaction).expect("the terms fit");
+    drop(terms);
+    let fill = start.elapsed();
+
+    let start = Instant::now();
+    let mut terms = S::load(&path).expect("the filled store loads");
+    let load = start.elapsed();
+    assert_eq!(terms.keys().len(), size);
+
+    let start = Instant::now();
+    for idx in (0..size).step_by(step) {
+        terms
+            .get(&format!("term{}/2", idx))
+            .expect("the term parses")
+            .expect("the term is there");
+    }
+    let get = per_operation(start);
+
+    let start = Instant::now();
+    for idx in (0..size).step_by(step) {
+        terms
+            .put(&format!("term{}/2", idx), term(idx, "changed"))
+            .expect("the edit fits");
+    }
+    let put = per_operation(start);
+
+    let start = Instant::now();
+    for idx in (0..size).step_by(step) {
+        terms
+            .delete(&format!("term{}/2", idx))
+            .expect("the term is there");
+    }
+    let delete = per_operation(start);
+
+    let start = Instant::now();
+    export(&terms);
+    let export = start.elapsed();
+
+    println!(
+        "{store:>10} {size:>7} terms: fill {fill:>10.2?}, load {load:>10.2?}, export {export:>10.2?} \
+         | per operation: get {get:>10.2?}, put {put:>10.2?}, delete {delete:>10.2?}"
+    );
+    drop(terms);
+    fs::remove_dir_all(&path).expect("the temp dir is removable");
+}
+
+fn main() {
+    // `cargo test --all-targets` runs this as well - without "--bench" only as a smoke test
+    let is_bench = std::env::args().any(|arg| arg == "--bench");
+    let sizes = if is_bench { &SIZES[..] } else { &SIZES[..1] };
+    for &size in sizes {
+        // page.pl is written on every change already
+        bench::<Terms>("page.pl", size, |_| {});
+        bench::<EmbeddedTerms>("embedded", size, |terms| {
+            terms.export().expect("page.pl is writable")
+        });
+    }
+}
@@ -1,9 +1,11 @@
 use std::{collections::HashMap, path::PathBuf};
 
 use its_logical::knowledge::store::in_memory::InMemoryTerms;
-use its_logical::knowledge::store::persistent::TermsWithEngine;
 use its_logical::knowledge::store::Load;
-use its_logical::knowledge::{model::fat_term::parse_fat_term, store::TermsStore};
+use its_logical::knowledge::{
+    model::fat_term::parse_fat_term,
+    store::{self, TermsStore},
+};
 
 pub struct ItsLogicalApp<T: TermsStore> {
     ui: crate::ui::App<T>,
@@ -11,8 +13,14 @@ pub struct ItsLogicalApp<T: TermsStore> {
 
 const SCALE_FACTOR: f32 = 1.2;
 
-impl ItsLogicalApp<TermsWithEngine> {
-    pub fn new(c: &eframe::CreationContext<'_>, knowledge_path: PathBuf) -> Self {
+impl<T> ItsLogicalApp<T>
+where
+    T: TermsStore + Load<Store = T>,
+{
+    pub fn new(
+        c: &eframe::CreationContext<'_>,
+        knowledge_path: PathBuf,
+    ) -> Result<Self, store::Error> {
         let mut style = (*c.egui_ctx.style()).clone();
 
         for (_, font) in style.text_styles.iter_mut() {
@@ -20,15 +28,15 @@ impl ItsLogicalApp<TermsWithEngine> {
         }
         c.egui_ctx.set_style(style);
 
-        Self {
-            ui: crate::ui::App::new(TermsWithEngine::load(&knowledge_path), knowledge_path),
-        }
+        Ok(Self {
+            ui: crate::ui::App::new(T::load(&knowledge_path)?, knowledge_path),
+        })
     }
 }
 
 impl ItsLogicalApp<InMemoryTerms> {
     #[allow(dead_code)] // keep this for the examples for now
-    pub fn new(c: &eframe::CreationContext<'_>) -> Self {
+    pub fn with_example(c: &eframe::CreationContext<'_>) -> Self {
         let mut style = (*c.egui_ctx.style()).clone();
 
         for (_, font) in style.text_styles.iter_mut() {
@@ -44,7 +52,7 @@ impl ItsLogicalApp<InMemoryTerms> {
 mother(Siika,Mircho).
 mother(Stefka,Petko).
 mother(Cecka,Krustio).
-mother(Mother,Child):-parent(Mother,Child),female(Mother)
+mother(Mother,Child):-parent(Mother,Child),female(Mother).
 ",
         )
         .unwrap();
@@ -55,7 +63,7 @@ mother(Mother,Child):-parent(Mother,Child),female(Mother)
 % @see 
 father(Stefan,Petko).
 father(Hristo,Stoichko).
-father(Father,Child):-parent(Father,Child),male(Father)
+father(Father,Child):-parent(Father,Child),male(Father).
 ",
         )
         .unwrap();
@@ -71,9 +79,9 @@ male(petko).
         Self {
             ui: crate::ui::App::new(
                 InMemoryTerms::new(HashMap::from([
-                    ("mother".to_string(), mother),
-                    ("father".to_string(), father),
-                    ("male".to_string(), male),
+                    (mother.key(), mother),
+                    (father.key(), father),
+                    (male.key(), male),
                 ])),
                 // in-memory mode doesn't care about base directory
                 PathBuf::new(),
@@ -82,15 +90,10 @@ male(petko).
     }
 }
 
-impl eframe::App for ItsLogicalApp<InMemoryTerms> {
-    /// Called each time the UI needs repainting, which may be many times per second.
-    /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.ui.show(ctx)
-    }
-}
-
-impl eframe::App for ItsLogicalApp<TermsWithEngine> {
+impl<T> eframe::App for ItsLogicalApp<T>
+where
+    T: TermsStore + Load<Store = T>,
+{
     /// Called each time the UI needs repainting, which may be many times per second.
     /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
@@ -1,24 +1,81 @@
-use std::collections::HashSet;
-
 use its_logical::{
     changes::{
         change::{Apply, Change},
         deletion::Deletion,
     },
-    knowledge::{self, model::fat_term::FatTerm},
+    knowledge::{
+        history::{self, History},
+        integrity::{self, IntegrityReport},
+        model::fat_term::FatTerm,
+        store::{self, Get, Transact},
+    },
 };
+use thiserror::Error;
 use tracing::debug;
 
 use crate::terms_cache::{
-    change_handling::{automatic, with_confirmation},
+    change_handling::{automatic, with_confirmation, FinishedCommitResult},
     NamedTerm, TermHolder, TermsCache, TwoPhaseTerm,
 };
 
+// the store is the source of truth - a change it took stays even if git doesn't take it
+#[derive(Error, Debug)]
+pub(crate) enum Error {
+    #[error("Can't save the change: {0}")]
+    Store(#[from] store::Error),
+    #[error("The change is saved, but not committed to git: {0}")]
+    History(#[from] history::Error),
+    #[error("Can't propagate the change: {0}")]
+    NotReady(with_confirmation::Error),
+}
+
+impl From<with_confirmation::Error> for Error {
+    fn from(err: with_confirmation::Error) -> Self {
+        match err {
+            with_confirmation::Error::Store(err) => Error::Store(err),
+            err => Error::NotReady(err),
+        }
+    }
+}
+
+// the terms a store commit wrote - by their keys after the commit
+#[derive(Default)]
+pub(crate) struct Written {
+    pub(crate) changed: Vec<String>,
+    pub(crate) deleted: Vec<String>,
+}
+
+// commits the module directory to its git repository, if it has one
+pub(crate) fn record(history: Option<&History>, mut written: Written) -> Result<(), Error> {
+    let Some(history) = history else {
+        return Ok(());
+    };
+    if written.changed.is_empty() && written.deleted.is_empty() {
+        return Ok(());
+    }
+    written.changed.sort();
+    written.deleted.sort();
+    history.record(&written.changed, &written.deleted)?;
+    Ok(())
+}
+
+// the @see lists the report found wrong, rewritten in a single store commit
+pub(crate) fn repair_references(
+    store: &mut (impl Get + Transact),
+    report: &IntegrityReport,
+) -> Result<Written, store::Error> {
+    Ok(Written {
+        changed: integrity::repair(store, report)?,
+        deleted: vec![],
+    })
+}
+
 pub(crate) fn propagate_change<T, K>(
     change: &Change,
-    store: &mut (impl knowledge::store::Get + knowledge::store::Put),
+    store: &mut (impl Get + Transact),
     cache: &mut TermsCache<T, K>,
-) where
+) -> Result<Written, Error>
+where
     T: NamedTerm + automatic::Apply,
     K: TwoPhaseTerm<Creator = T> + automatic::Apply + with_confirmation::Apply,
 {
@@ -29,7 +86,7 @@ pub(crate) fn propagate_change<T, K>(
 
     debug!(
         "Changes made for {}. Propagating to: {:?}",
-        change.original().meta.term.name,
+        change.original().key(),
         all_affected
     );
 
@@ -38,58 +95,64 @@ pub(crate) fn propagate_change<T, K>(
         || /* no referring term is affected */ referred_by.is_empty();
 
     let change_source_in_commit = cache
-        .get(&change.original().meta.term.name)
+        .get(&change.original().key())
         .map(|t| matches!(t, TermHolder::TwoPhase(_)))
         .unwrap_or(false);
 
+    let mut written = Written::default();
     if is_automatic && !change_source_in_commit {
         debug!("automatic propagation");
-        cache.apply_automatic_change(change);
-
-        let mut changes_for_store = store.apply(change);
-        changes_for_store.insert(
-            change.original().meta.term.name.clone(),
-            change.changed().to_owned(),
-        );
+        let mut changes_for_store = store.apply(change)?;
+        changes_for_store.insert(change.original().key(), change.changed().to_owned());
 
-        for (term_name, with_applied_change) in changes_for_store {
-            store.put(&term_name, with_applied_change);
+        let mut transaction = store.begin();
+        for (term_key, with_applied_change) in changes_for_store {
+            written.changed.push(with_applied_change.key());
+            transaction.put(&term_key, with_applied_change);
         }
+        store.commit(transaction)?;
+        // the cache follows the store only once the store has taken the change
+        cache.apply_automatic_change(change);
     } else {
         debug!("2 phase commit propagation");
-        cache
-            .apply_for_confirmation_change(store, change)
-            .expect("no errors are currently expected");
+        cache.apply_for_confirmation_change(store, change)?;
     }
     // if there is an ongoing 2phase commit among one of `updated_term`'s newly mentioned terms,
     // all the changes in the commit need to be applied on `updated_term`
     if cache.iter().any(|t| matches!(t, TermHolder::TwoPhase(_))) {
         cache.repeat_ongoing_commit_changes(change, is_automatic);
     }
+    Ok(written)
 }
 
 pub(crate) fn finish_commit<T, K>(
-    store: &mut (impl knowledge::store::Get + knowledge::store::Put + knowledge::store::Delete),
+    store: &mut impl Transact,
     cache: &mut TermsCache<T, K>,
-) -> HashSet<String>
+) -> Result<Written, store::Error>
 where
     T: NamedTerm,
     K: TwoPhaseTerm<Creator = T>,
 {
-    let changed_terms = cache.finish_commit();
-    let mut deleted = HashSet::new();
-    for (changed_term_original_name, change) in changed_terms {
-        match change {
-            crate::terms_cache::change_handling::FinishedCommitResult::Changed(changed_term) => {
-                store.put(&changed_term_original_name, changed_term);
-            }
-            crate::terms_cache::change_handling::FinishedCommitResult::Deleted => {
-                store.delete(&changed_term_original_name);
-                deleted.insert(changed_term_original_name);
-            }
+    let commit_results = cache.commit_results();
+    let mut written = Written::default();
+    let mut transaction = store.begin();
+    // deletes go first, so that a term deleted under the key another one is renamed to doesn't
+    // take the renamed one with it
+    for (term_key, result) in &commit_results {
+        if let FinishedCommitResult::Deleted = result {
+            transaction.delete(term_key);
+            written.deleted.push(term_key.to_owned());
+        }
+    }
+    for (term_key, result) in commit_results {
+        if let FinishedCommitResult::Changed(changed_term) = result {
+            written.changed.push(changed_term.key());
+            transaction.put(&term_key, changed_term);
         }
     }
-    deleted
+    store.commit(transaction)?;
+    cache.finish_commit();
+    Ok(written)
 }
 
 pub(crate) fn revert_commit<T, K>(cache: &mut TermsCache<T, K>)
@@ -102,26 +165,30 @@ where
 
 pub(crate) fn propagate_deletion<T, K>(
     term: &FatTerm,
-    store: &mut (impl knowledge::store::Get + knowledge::store::Put + knowledge::store::Delete),
+    store: &mut (impl Get + Transact),
     cache: &mut TermsCache<T, K>,
-) -> bool
+) -> Result<Written, Error>
 where
     T: NamedTerm + automatic::Apply,
     K: TwoPhaseTerm<Creator = T> + automatic::Apply + with_confirmation::Apply,
 {
     if term.meta.referred_by.is_empty() {
         debug!("automatic deletion");
-        cache.apply_automatic_deletion(term);
-        for (term_name, with_applied_deletion) in term.apply_deletion(store) {
-            store.put(&term_name, with_applied_deletion);
+        let mut written = Written::default();
+        let mut transaction = store.begin();
+        for (term_key, with_applied_deletion) in term.apply_deletion(store)? {
+            written.changed.push(with_applied_deletion.key());
+            transaction.put(&term_key, with_applied_deletion);
         }
-        store.delete(&term.meta.term.name);
-        true
+        transaction.delete(&term.key());
+        store.commit(transaction)?;
+        cache.apply_automatic_deletion(term);
+        written.deleted.push(term.key());
+        Ok(written)
     } else {
         debug!("deletion with confirmation");
-        cache
-            .apply_for_confirmation_delete(term, store)
-            .expect("no errors are currently expected");
-        false
+        cache.apply_for_confirmation_delete(term, store)?;
+        // nothing is written until the deletion is confirmed
+        Ok(Written::default())
     }
 }
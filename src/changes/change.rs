@@ -3,8 +3,9 @@ use std::collections::HashMap;
 use crate::knowledge::{
     self,
     model::{
-        comment::name_description::NameDescription, fat_term::FatTerm,
-        term::args_binding::ArgsBinding,
+        comment::name_description::NameDescription,
+        fat_term::FatTerm,
+        term::{arg::Arg, args_binding::ArgsBinding},
     },
 };
 
@@ -24,15 +25,14 @@ pub enum ArgsChange {
 }
 
 impl ArgsChange {
-    pub fn apply(&self, binding: &mut ArgsBinding) -> Option<String> {
+    pub fn apply(&self, binding: &mut ArgsBinding) -> Option<Arg> {
         match self {
-            ArgsChange::Pushed(_) => binding.binding.push("_".to_string()),
+            ArgsChange::Pushed(_) => binding.binding.push(Arg::Variable("_".to_string())),
             ArgsChange::Moved(moves) => {
-                let mut projected = vec!["".to_string(); binding.binding.len()];
-
-                for (idx, old_idx) in moves.iter().enumerate() {
-                    projected[idx] = binding.binding[*old_idx].clone();
-                }
+                let projected = moves
+                    .iter()
+                    .map(|old_idx| binding.binding[*old_idx].clone())
+                    .collect();
                 binding.binding = projected;
             }
             ArgsChange::Removed(removed_idx) => return Some(binding.binding.remove(*removed_idx)),
@@ -42,70 +42,58 @@ impl ArgsChange {
 }
 
 pub trait Apply {
-    fn apply(&self, change: &Change) -> HashMap<String, FatTerm>;
+    // the terms the change has to be propagated to - Err when one of them can't be read
+    fn apply(&self, change: &Change) -> Result<HashMap<String, FatTerm>, knowledge::store::Error>;
 }
 
 impl<T: knowledge::store::Get> Apply for T {
-    fn apply(&self, change: &Change) -> HashMap<String, FatTerm> {
+    fn apply(&self, change: &Change) -> Result<HashMap<String, FatTerm>, knowledge::store::Error> {
         let mut terms_cache = TermsCache::new(self);
+        let original_key = change.original.key();
+        let changed_key = change.changed.key();
 
-        if !change.args_changes.is_empty() {
-            for referred_by_term_name in &change.changed.meta.referred_by {
-                if let Some(term) = terms_cache.get(referred_by_term_name) {
-                    apply_args_changes(change, term);
+        if !change.args_changes.is_empty() || original_key != changed_key {
+            for referred_by_term_key in &change.changed.meta.referred_by {
+                if let Some(term) = terms_cache.get(referred_by_term_key)? {
+                    update_calls(change, term);
                 }
             }
         }
 
         let (new, removed) = changes_in_mentioned_terms(change);
-        for term_name_with_removed_mention in &removed {
-            if let Some(term) = terms_cache.get(term_name_with_removed_mention) {
-                term.remove_referred_by(&change.original.meta.term.name);
+        for term_key_with_removed_mention in &removed {
+            if let Some(term) = terms_cache.get(term_key_with_removed_mention)? {
+                term.remove_referred_by(&original_key);
             }
         }
 
-        for term_name_with_new_mention in &new {
-            if let Some(term) = terms_cache.get(term_name_with_new_mention) {
-                term.add_referred_by(&change.original.meta.term.name);
+        for term_key_with_new_mention in &new {
+            if let Some(term) = terms_cache.get(term_key_with_new_mention)? {
+                term.add_referred_by(&original_key);
             }
         }
-        // once all externally propagated changes are applied with the original name,
-        // the potential name change is addressed
-        if change.original.meta.term.name != change.changed.meta.term.name {
+        // once all externally propagated changes are applied with the original key,
+        // the potential name or arity change is addressed
+        if original_key != changed_key {
             for rule in change.changed.term.rules.iter() {
-                for body_term in &rule.body {
-                    if let Some(term) = terms_cache.get(&body_term.name) {
-                        term.rename_referred_by(
-                            &change.original.meta.term.name,
-                            &change.changed.meta.term.name,
-                        );
-                    }
-                }
-            }
-
-            for referred_by_term_name in &change.changed.meta.referred_by {
-                if let Some(term) = terms_cache.get(referred_by_term_name) {
-                    for rule in &mut term.term.rules {
-                        for body_term in &mut rule.body {
-                            if body_term.name == change.original.meta.term.name {
-                                body_term.name = change.changed.meta.term.name.clone();
-                            }
-                        }
+                for body_term in rule.body_terms() {
+                    if let Some(term) = terms_cache.get(&body_term.key())? {
+                        term.rename_referred_by(&original_key, &changed_key);
                     }
                 }
             }
         }
-        terms_cache.all_terms()
+        Ok(terms_cache.all_terms())
     }
 }
 
 // enable applying a change on a single term
 impl knowledge::store::Get for FatTerm {
-    fn get(&self, term_name: &str) -> Option<FatTerm> {
-        if term_name == self.meta.term.name {
-            Some(self.clone())
+    fn get(&self, term_key: &str) -> Result<Option<FatTerm>, knowledge::store::Error> {
+        if term_key == self.key() {
+            Ok(Some(self.clone()))
         } else {
-            None
+            Ok(None)
         }
     }
 }
@@ -133,14 +121,14 @@ impl Change {
 
     // returned "mentioned" terms are ones that actually are changed (newly mentioned/not mentioned
     // any longer or in case of name change each mentioned term will have its "referred by" field
-    // changed)
+    // changed). A change of the arity is as much of a change of identity as a rename
     pub fn affects(&self) -> (Vec<String>, Vec<String>) {
         let mut mentioned = vec![];
         let mut referred_by = vec![];
         let mut include_referred_by = false;
         let mut include_mentioned = false;
 
-        if self.original.meta.term.name != self.changed.meta.term.name {
+        if self.original.key() != self.changed.key() {
             include_referred_by = true;
             include_mentioned = true;
         }
@@ -188,13 +176,17 @@ fn changes_in_mentioned_terms(change: &Change) -> (Vec<String>, Vec<String>) {
     )
 }
 
-fn apply_args_changes(change: &Change, target_term: &mut FatTerm) {
+// the calls to the changed term get its new args and name - calls with a different arity are
+// calls to a different term and are left alone
+fn update_calls(change: &Change, target_term: &mut FatTerm) {
+    let original_key = change.original.key();
     for rule in &mut target_term.term.rules {
-        for body_term in &mut rule.body {
-            if body_term.name == change.original.meta.term.name {
+        for body_term in rule.body_terms_mut() {
+            if body_term.key() == original_key {
                 for change in &change.args_changes {
                     change.apply(&mut body_term.arg_bindings);
                 }
+                body_term.name = change.changed.meta.term.name.clone();
             }
         }
     }
@@ -5,7 +5,10 @@ use crate::knowledge::{self, model::fat_term::FatTerm};
 use super::terms_cache::TermsCache;
 
 pub trait Deletion {
-    fn apply_deletion(&self, terms: &impl knowledge::store::Get) -> HashMap<String, FatTerm>;
+    fn apply_deletion(
+        &self,
+        terms: &impl knowledge::store::Get,
+    ) -> Result<HashMap<String, FatTerm>, knowledge::store::Error>;
     fn affects(&self) -> &[String];
 }
 
@@ -14,23 +17,29 @@ impl Deletion for FatTerm {
         &self.meta.referred_by
     }
 
-    fn apply_deletion(&self, terms: &impl knowledge::store::Get) -> HashMap<String, FatTerm> {
+    fn apply_deletion(
+        &self,
+        terms: &impl knowledge::store::Get,
+    ) -> Result<HashMap<String, FatTerm>, knowledge::store::Error> {
         let mut terms_cache = TermsCache::new(terms);
-        for mentioned_term_name in self.mentioned_terms().into_iter() {
-            if let Some(term) = terms_cache.get(&mentioned_term_name) {
-                term.remove_referred_by(&self.meta.term.name);
+        let key = self.key();
+        for mentioned_term_key in self.mentioned_terms().into_iter() {
+            if let Some(term) = terms_cache.get(&mentioned_term_key)? {
+                term.remove_referred_by(&key);
             }
         }
 
-        for referred_by_term_name in &self.meta.referred_by {
-            if let Some(term) = terms_cache.get(referred_by_term_name) {
+        for referred_by_term_key in &self.meta.referred_by {
+            if let Some(term) = terms_cache.get(referred_by_term_key)? {
                 for rule in &mut term.term.rules {
-                    rule.body
-                        .retain(|body_term| body_term.name != self.meta.term.name);
+                    rule.body = std::mem::take(&mut rule.body)
+                        .into_iter()
+                        .filter_map(|goal| goal.without_term(&key))
+                        .collect();
                 }
                 term.term.rules.retain(|rule| !rule.body.is_empty());
             }
         }
-        terms_cache.all_terms()
+        Ok(terms_cache.all_terms())
     }
 }
@@ -8,7 +8,10 @@ mod tests {
     use crate::knowledge::model::{
         comment::{name_description::NameDescription, Comment},
         fat_term::FatTerm,
-        term::{args_binding::ArgsBinding, bound_term::BoundTerm, rule::Rule, Term},
+        term::{
+            arg::Arg, args_binding::ArgsBinding, bound_term::BoundTerm, goal::Goal, rule::Rule,
+            Term,
+        },
     };
     use std::collections::HashSet;
 
@@ -21,45 +24,45 @@ mod tests {
             ),
             Term::new(
                 &[ArgsBinding {
-                    binding: vec!["fact_value".to_string()],
+                    binding: vec![Arg::Atom("fact_value".to_string())],
                 }],
                 &[
                     Rule {
                         head: ArgsBinding {
-                            binding: vec!["FirstHeadArg".to_string()],
+                            binding: vec![Arg::Variable("FirstHeadArg".to_string())],
                         },
                         body: vec![
-                            BoundTerm {
+                            Goal::Term(BoundTerm {
                                 name: "first_body_term".to_string(),
                                 arg_bindings: ArgsBinding {
-                                    binding: vec!["with_some_arg".to_string()],
+                                    binding: vec![Arg::Atom("with_some_arg".to_string())],
                                 },
-                            },
-                            BoundTerm {
+                            }),
+                            Goal::Term(BoundTerm {
                                 name: "second_body_term".to_string(),
                                 arg_bindings: ArgsBinding {
-                                    binding: vec!["with_some_arg2".to_string()],
+                                    binding: vec![Arg::Atom("with_some_arg2".to_string())],
                                 },
-                            },
+                            }),
                         ],
                     },
                     Rule {
                         head: ArgsBinding {
-                            binding: vec!["FirstHeadArg2".to_string()],
+                            binding: vec![Arg::Variable("FirstHeadArg2".to_string())],
                         },
                         body: vec![
-                            BoundTerm {
+                            Goal::Term(BoundTerm {
                                 name: "test".to_string(),
                                 arg_bindings: ArgsBinding {
-                                    binding: vec!["FirstHeadArg2".to_string()],
+                                    binding: vec![Arg::Variable("FirstHeadArg2".to_string())],
                                 },
-                            },
-                            BoundTerm {
+                            }),
+                            Goal::Term(BoundTerm {
                                 name: "second_body_term2".to_string(),
                                 arg_bindings: ArgsBinding {
-                                    binding: vec!["with_some_arg2".to_string()],
+                                    binding: vec![Arg::Atom("with_some_arg2".to_string())],
                                 },
-                            },
+                            }),
                         ],
                     },
                 ],
@@ -72,7 +75,7 @@ mod tests {
             Comment::new(
                 NameDescription::new("first_body_term", "first body term description"),
                 &[NameDescription::new("FirstArg", "First arg's description")],
-                &["test".to_string()],
+                &["test/1".to_string()],
             ),
             Term::new(&[], &[]),
         )
@@ -83,49 +86,49 @@ mod tests {
             Comment::new(
                 NameDescription::new("test", "test description"),
                 &[NameDescription::new("FirstArg", "First arg's description")],
-                &["first_related".to_string()],
+                &["first_related/1".to_string()],
             ),
             Term::new(
                 &[ArgsBinding {
-                    binding: vec!["fact_value".to_string()],
+                    binding: vec![Arg::Atom("fact_value".to_string())],
                 }],
                 &[
                     Rule {
                         head: ArgsBinding {
-                            binding: vec!["FirstHeadArg".to_string()],
+                            binding: vec![Arg::Variable("FirstHeadArg".to_string())],
                         },
                         body: vec![
-                            BoundTerm {
+                            Goal::Term(BoundTerm {
                                 name: "first_body_term".to_string(),
                                 arg_bindings: ArgsBinding {
-                                    binding: vec!["with_some_arg".to_string()],
+                                    binding: vec![Arg::Atom("with_some_arg".to_string())],
                                 },
-                            },
-                            BoundTerm {
+                            }),
+                            Goal::Term(BoundTerm {
                                 name: "second_body_term".to_string(),
                                 arg_bindings: ArgsBinding {
-                                    binding: vec!["with_some_arg2".to_string()],
+                                    binding: vec![Arg::Atom("with_some_arg2".to_string())],
                                 },
-                            },
+                            }),
                         ],
                     },
                     Rule {
                         head: ArgsBinding {
-                            binding: vec!["FirstHeadArg2".to_string()],
+                            binding: vec![Arg::Variable("FirstHeadArg2".to_string())],
                         },
                         body: vec![
-                            BoundTerm {
+                            Goal::Term(BoundTerm {
                                 name: "first_body_term2".to_string(),
                                 arg_bindings: ArgsBinding {
-                                    binding: vec!["with_some_arg".to_string()],
+                                    binding: vec![Arg::Atom("with_some_arg".to_string())],
                                 },
-                            },
-                            BoundTerm {
+                            }),
+                            Goal::Term(BoundTerm {
                                 name: "second_body_term2".to_string(),
                                 arg_bindings: ArgsBinding {
-                                    binding: vec!["with_some_arg2".to_string()],
+                                    binding: vec![Arg::Atom("with_some_arg2".to_string())],
                                 },
-                            },
+                            }),
                         ],
                     },
                 ],
@@ -149,11 +152,11 @@ mod tests {
 
         affected.sort();
         let mut expected = vec![
-            "first_related".to_string(),
-            "first_body_term".to_string(),
-            "second_body_term".to_string(),
-            "first_body_term2".to_string(),
-            "second_body_term2".to_string(),
+            "first_related/1".to_string(),
+            "first_body_term/1".to_string(),
+            "second_body_term/1".to_string(),
+            "first_body_term2/1".to_string(),
+            "second_body_term2/1".to_string(),
         ];
 
         expected.sort();
@@ -182,7 +185,7 @@ mod tests {
 
         let mut with_facts_change = original.clone();
         with_facts_change.term.facts.push(ArgsBinding {
-            binding: vec!["SomeArgValue".to_string()],
+            binding: vec![Arg::Variable("SomeArgValue".to_string())],
         });
 
         let change = Change::new(original, &[], with_facts_change);
@@ -219,14 +222,14 @@ mod tests {
         let mut with_rules_change = original.clone();
         let new_rule = Rule {
             head: ArgsBinding {
-                binding: vec!["Arg".to_string()],
+                binding: vec![Arg::Variable("Arg".to_string())],
             },
-            body: vec![BoundTerm {
+            body: vec![Goal::Term(BoundTerm {
                 name: "new_rule_body_term".to_string(),
                 arg_bindings: ArgsBinding {
-                    binding: vec!["with_some_arg".to_string()],
+                    binding: vec![Arg::Atom("with_some_arg".to_string())],
                 },
-            }],
+            })],
         };
 
         // remove the first and add another
@@ -241,7 +244,11 @@ mod tests {
                 .collect();
 
         affected.sort();
-        let mut expected = vec!["first_body_term", "second_body_term", "new_rule_body_term"];
+        let mut expected = vec![
+            "first_body_term/1",
+            "second_body_term/1",
+            "new_rule_body_term/1",
+        ];
         expected.sort();
 
         assert_eq!(affected, expected);
@@ -261,11 +268,22 @@ mod tests {
             with_arg_change,
         );
         let (mentioned, referred_by) = change.affects();
-        let affected: Vec<String> =
+        let mut affected: Vec<String> =
             HashSet::<String>::from_iter(mentioned.into_iter().chain(referred_by))
                 .into_iter()
                 .collect();
-        assert_eq!(affected, vec!["first_related".to_string()]);
+        affected.sort();
+
+        // the key changes with the arity, so the mentioned terms need their @see updated too
+        let mut expected = vec![
+            "first_related/1",
+            "first_body_term/1",
+            "second_body_term/1",
+            "first_body_term2/1",
+            "second_body_term2/1",
+        ];
+        expected.sort();
+        assert_eq!(affected, expected);
     }
 
     #[test]
@@ -275,14 +293,14 @@ mod tests {
         let mut with_changes = original.clone();
         let new_rule = Rule {
             head: ArgsBinding {
-                binding: vec!["Arg".to_string()],
+                binding: vec![Arg::Variable("Arg".to_string())],
             },
-            body: vec![BoundTerm {
+            body: vec![Goal::Term(BoundTerm {
                 name: "new_rule_body_term".to_string(),
                 arg_bindings: ArgsBinding {
-                    binding: vec!["with_some_arg".to_string()],
+                    binding: vec![Arg::Atom("with_some_arg".to_string())],
                 },
-            }],
+            })],
         };
 
         // remove the first add another
@@ -305,10 +323,12 @@ mod tests {
         affected.sort();
 
         let mut expected = vec![
-            "first_related",
-            "first_body_term",
-            "second_body_term",
-            "new_rule_body_term",
+            "first_related/1",
+            "first_body_term/1",
+            "second_body_term/1",
+            "first_body_term2/1",
+            "second_body_term2/1",
+            "new_rule_body_term/1",
         ];
         expected.sort();
         assert_eq!(affected, expected);
@@ -328,20 +348,21 @@ mod tests {
         let change = Change::new(original, &[arg_change], after_change.clone());
         let result = related_term
             .apply(&change)
-            .get(&related_term.meta.term.name)
+            .unwrap()
+            .get(&related_term.key())
             .unwrap()
             .to_owned();
 
         let mut expected = related_term;
         let idx = expected.term.rules[1]
-            .body
-            .iter_mut()
+            .body_terms()
+            .iter()
             .position(|x| x.name == "test")
             .unwrap();
-        expected.term.rules[1].body[idx]
+        expected.term.rules[1].body_terms_mut()[idx]
             .arg_bindings
             .binding
-            .push("_".to_string());
+            .push(Arg::Variable("_".to_string()));
 
         assert_eq!(result, expected);
 
@@ -352,16 +373,17 @@ mod tests {
         let change = Change::new(original, &[ArgsChange::Moved(vec![1, 0])], after_change);
         let result = expected
             .apply(&change)
-            .get(&expected.meta.term.name)
+            .unwrap()
+            .get(&expected.key())
             .unwrap()
             .to_owned();
 
         let idx = expected.term.rules[1]
-            .body
-            .iter_mut()
+            .body_terms()
+            .iter()
             .position(|x| x.name == "test")
             .unwrap();
-        expected.term.rules[1].body[idx]
+        expected.term.rules[1].body_terms_mut()[idx]
             .arg_bindings
             .binding
             .swap(1, 0);
@@ -380,18 +402,22 @@ mod tests {
         let change = Change::new(original, &[ArgsChange::Removed(0)], after_change);
         let result = related_term
             .apply(&change)
-            .get(&related_term.meta.term.name)
+            .unwrap()
+            .get(&related_term.key())
             .unwrap()
             .to_owned();
 
         let mut expected = related_term;
 
         let idx = expected.term.rules[1]
-            .body
-            .iter_mut()
+            .body_terms()
+            .iter()
             .position(|x| x.name == "test")
             .unwrap();
-        expected.term.rules[1].body[idx].arg_bindings.binding.pop();
+        expected.term.rules[1].body_terms_mut()[idx]
+            .arg_bindings
+            .binding
+            .pop();
 
         assert_eq!(result, expected);
     }
@@ -403,12 +429,13 @@ mod tests {
         after_changes.term.rules[0].body.remove(0);
 
         let mentioned = create_mentioned_term();
-        assert_eq!(mentioned.meta.referred_by, vec!["test"]);
+        assert_eq!(mentioned.meta.referred_by, vec!["test/1"]);
 
         let change = Change::new(original, &[], after_changes);
         let result = mentioned
             .apply(&change)
-            .get(&mentioned.meta.term.name)
+            .unwrap()
+            .get(&mentioned.key())
             .unwrap()
             .to_owned();
         let mut expected = mentioned;
@@ -424,32 +451,78 @@ mod tests {
         updated.meta.term.name = "new_name_who_dis".to_string();
 
         let mentioned = create_mentioned_term();
-        assert_eq!(mentioned.meta.referred_by, vec!["test"]);
+        assert_eq!(mentioned.meta.referred_by, vec!["test/1"]);
         let related = create_related_test_term();
 
         let change = Change::new(original.clone(), &[], updated.clone());
         let changed_mentioned = mentioned
             .apply(&change)
-            .get(&mentioned.meta.term.name)
+            .unwrap()
+            .get(&mentioned.key())
             .unwrap()
             .to_owned();
         let mut expected_changed_mentioned = mentioned;
-        expected_changed_mentioned.meta.referred_by = vec![updated.meta.term.name.clone()];
+        expected_changed_mentioned.meta.referred_by = vec![updated.key()];
         assert_eq!(changed_mentioned, expected_changed_mentioned);
 
         let change = Change::new(original, &[], updated.clone());
         let changed_related = related
             .apply(&change)
-            .get(&related.meta.term.name)
+            .unwrap()
+            .get(&related.key())
             .unwrap()
             .to_owned();
         let mut expected_changed_related = related;
         let idx = expected_changed_related.term.rules[1]
-            .body
-            .iter_mut()
+            .body_terms()
+            .iter()
             .position(|x| x.name == "test")
             .unwrap();
-        expected_changed_related.term.rules[1].body[idx].name = updated.meta.term.name;
+        expected_changed_related.term.rules[1].body_terms_mut()[idx].name = updated.meta.term.name;
         assert_eq!(changed_related, expected_changed_related);
     }
+
+    #[test]
+    fn test_apply_arity_change() {
+        let original = create_test_term();
+        let mut updated = original.clone();
+        updated.meta.args.push(NameDescription::new(
+            "SecondArg",
+            "Second arg's description",
+        ));
+
+        let mentioned = create_mentioned_term();
+        let change = Change::new(
+            original.clone(),
+            &[ArgsChange::Pushed(NameDescription::new("SecondArg", ""))],
+            updated.clone(),
+        );
+        let changed_mentioned = mentioned
+            .apply(&change)
+            .unwrap()
+            .get(&mentioned.key())
+            .unwrap()
+            .to_owned();
+        assert_eq!(changed_mentioned.meta.referred_by, vec!["test/2"]);
+
+        // a call to test/0 is a call to another term, so only the call to test/1 gets the new arg
+        let mut related = create_related_test_term();
+        related.term.rules[1]
+            .body
+            .push(Goal::Term(BoundTerm::new("test", ArgsBinding::new(&[]))));
+        let changed_related = related
+            .apply(&change)
+            .unwrap()
+            .get(&related.key())
+            .unwrap()
+            .to_owned();
+
+        let calls: Vec<String> = changed_related.term.rules[1]
+            .body_terms()
+            .iter()
+            .filter(|x| x.name == "test")
+            .map(|x| x.key())
+            .collect();
+        assert_eq!(calls, vec!["test/2", "test/0"]);
+    }
 }
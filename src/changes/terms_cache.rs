@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use crate::knowledge::{model::fat_term::FatTerm, store::Get};
+use crate::knowledge::{
+    model::fat_term::FatTerm,
+    store::{Error, Get},
+};
 
 pub(crate) struct TermsCache<'a, T: Get> {
     updated_terms: HashMap<String, FatTerm>,
@@ -14,13 +17,14 @@ impl<'a, T: Get> TermsCache<'a, T> {
             terms,
         }
     }
-    pub(crate) fn get<'b>(&'b mut self, name: &str) -> Option<&'b mut FatTerm> {
+    // a term that can't be read fails the whole change - changing the rest would leave it with
+    // stale calls and @see
+    pub(crate) fn get<'b>(&'b mut self, name: &str) -> Result<Option<&'b mut FatTerm>, Error> {
         match self.updated_terms.entry(name.to_string()) {
-            std::collections::hash_map::Entry::Occupied(e) => Some(e.into_mut()),
-            std::collections::hash_map::Entry::Vacant(e) => match self.terms.get(name) {
-                Some(term) => Some(e.insert(term)),
-                None => None,
-            },
+            std::collections::hash_map::Entry::Occupied(e) => Ok(Some(e.into_mut())),
+            std::collections::hash_map::Entry::Vacant(e) => {
+                Ok(self.terms.get(name)?.map(|term| e.insert(term)))
+            }
         }
     }
 
@@ -38,8 +42,17 @@ mod tests {
     }
 
     impl Get for MockTerms {
-        fn get(&self, term_name: &str) -> Option<FatTerm> {
-            self.terms.get(term_name).cloned()
+        fn get(&self, term_name: &str) -> Result<Option<FatTerm>, Error> {
+            Ok(self.terms.get(term_name).cloned())
+        }
+    }
+
+    // every term is there, but none of them parses
+    struct UnreadableTerms;
+
+    impl Get for UnreadableTerms {
+        fn get(&self, term_name: &str) -> Result<Option<FatTerm>, Error> {
+            Err(Error::Engine(format!("{} is unreadable", term_name)))
         }
     }
 
@@ -62,13 +75,32 @@ mod tests {
 
         let mut terms_cache = TermsCache::new(&mock_terms);
 
-        assert_eq!(terms_cache.get(&term1_name), Some(&mut term1));
-        assert_eq!(terms_cache.get(&term2_name), Some(&mut term2));
+        assert_eq!(terms_cache.get(&term1_name).unwrap(), Some(&mut term1));
+        assert_eq!(terms_cache.get(&term2_name).unwrap(), Some(&mut term2));
 
-        terms_cache.get(&term1_name).unwrap().meta.term.desc = "some description".to_string();
+        terms_cache
+            .get(&term1_name)
+            .unwrap()
+            .unwrap()
+            .meta
+            .term
+            .desc = "some description".to_string();
         assert_eq!(
-            terms_cache.get(&term1_name).unwrap().meta.term.desc,
+            terms_cache
+                .get(&term1_name)
+                .unwrap()
+                .unwrap()
+                .meta
+                .term
+                .desc,
             "some description".to_string()
         );
     }
+
+    #[test]
+    fn test_unreadable_term() {
+        let unreadable = UnreadableTerms;
+        let mut terms_cache = TermsCache::new(&unreadable);
+        assert!(matches!(terms_cache.get("term1"), Err(Error::Engine(_))));
+    }
 }
@@ -0,0 +1,418 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use git2::{Commit, FileMode, ObjectType, Oid, Repository, Signature, Sort, Tree};
+use thiserror::Error;
+
+use super::{
+    model::{
+        fat_term::{parse_fat_term, FatTerm},
+        parsing::ParseError,
+    },
+    store::{is_knowledge_file, past::PastTerms},
+};
+
+// Every change written to the store of a module that is a git repository is committed to it, so
+// the history of a term is the history of the repository. Past versions are read from the
+// committed files - page.pl or the files of a one-file-per-term module alike
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("git failed: {0}")]
+    Git(#[from] git2::Error),
+    #[error("can't read the module directory: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    // neither a revision git knows nor a YYYY-MM-DD date with a commit on or before it
+    #[error("no revision {0}")]
+    NoRevision(String),
+}
+
+// a commit that changed the term
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revision {
+    // the hex id of the commit
+    pub id: String,
+    // seconds since the epoch
+    pub time: i64,
+    pub summary: String,
+    // the commit deleted the term
+    pub is_deletion: bool,
+}
+
+pub struct History {
+    repo: Repository,
+}
+
+// when git knows nothing about who is committing
+const FALLBACK_AUTHOR: (&str, &str) = ("It's Logical", "its-logical@localhost");
+
+impl History {
+    // None when the module directory is not the root of a git repository - a repository further up
+    // is never committed to
+    pub fn open(module_path: &Path) -> Result<Option<Self>, Error> {
+        match Repository::open(module_path) {
+            Ok(repo)
+                if repo
+                    .workdir()
+                    .is_some_and(|workdir| same_dir(workdir, module_path)) =>
+            {
+                Ok(Some(Self { repo }))
+            }
+            Ok(_) => Ok(None),
+            Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // commits the knowledge files of the module - None when they didn't change since the last
+    // commit. The tree is the last commit's with only the knowledge files replaced, so anything
+    // else in the module directory - staged or not - is left for whoever put it there
+    pub fn record(&self, changed: &[String], deleted: &[String]) -> Result<Option<String>, Error> {
+        let parent = match self.repo.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(err) if err.code() == git2::ErrorCode::UnbornBranch => None,
+            Err(err) => return Err(err.into()),
+        };
+        let parent_tree = parent.as_ref().map(Commit::tree).transpose()?;
+        let workdir = self.repo.workdir().ok_or_else(|| {
+            git2::Error::from_str("the repository of the module has no working directory")
+        })?;
+
+        let mut tree = self.repo.treebuilder(parent_tree.as_ref())?;
+        // the index follows the commit for the knowledge files only - what else is staged stays
+        let mut index = self.repo.index()?;
+        let mut on_disk = HashSet::new();
+        for dir_entry in fs::read_dir(workdir)? {
+            let dir_entry = dir_entry?;
+            let Ok(file_name) = dir_entry.file_name().into_string() else {
+                continue;
+            };
+            if !is_knowledge_file(&file_name) || !dir_entry.file_type()?.is_file() {
+                continue;
+            }
+            let blob = self.repo.blob_path(&dir_entry.path())?;
+            tree.insert(&file_name, blob, FileMode::Blob.into())?;
+            index.add_path(Path::new(&file_name))?;
+            on_disk.insert(file_name);
+        }
+        for entry in parent_tree.iter().flat_map(Tree::iter) {
+            let Some(file_name) = entry.name() else {
+                continue;
+            };
+            if is_knowledge_file(file_name) && !on_disk.contains(file_name) {
+                tree.remove(file_name)?;
+                index.remove_path(Path::new(file_name))?;
+            }
+        }
+        index.write()?;
+        let tree = self.repo.find_tree(tree.write()?)?;
+        if parent.as_ref().is_some_and(|p| p.tree_id() == tree.id()) {
+            return Ok(None);
+        }
+
+        let signature = self
+            .repo
+            .signature()
+            .or_else(|_| Signature::now(FALLBACK_AUTHOR.0, FALLBACK_AUTHOR.1))?;
+        let parents: Vec<_> = parent.iter().collect();
+        let id = self.repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &message(changed, deleted),
+            &tree,
+            &parents,
+        )?;
+        Ok(Some(id.to_string()))
+    }
+
+    // a revision git can resolve - a commit id, a branch, "HEAD~3" - or a YYYY-MM-DD date, which
+    // is the last commit made on or before that day (UTC), following first parents from HEAD
+    pub fn find_revision(&self, revision: &str) -> Result<String, Error> {
+        let no_revision = || Error::NoRevision(revision.to_owned());
+        let Some(end_of_day) = parse_date(revision).map(|day_start| day_start + SECONDS_IN_DAY)
+        else {
+            let commit = self
+                .repo
+                .revparse_single(revision)
+                .map_err(|_| no_revision())?
+                .peel_to_commit()?;
+            return Ok(commit.id().to_string());
+        };
+
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push_head().map_err(|_| no_revision())?;
+        revwalk.simplify_first_parent()?;
+        // the latest first, however the history was merged or rebased
+        revwalk.set_sorting(Sort::TIME)?;
+        for id in revwalk {
+            let commit = self.repo.find_commit(id?)?;
+            if commit.time().seconds() < end_of_day {
+                return Ok(commit.id().to_string());
+            }
+        }
+        Err(no_revision())
+    }
+
+    // the knowledge as it was committed in `revision` - see find_revision for getting one
+    pub fn knowledge_at(&self, revision: &str) -> Result<PastTerms, Error> {
+        let commit = self.find_commit(revision)?;
+        knowledge(&self.repo, knowledge_files(&commit.tree()?).into_values())
+    }
+
+    fn find_commit(&self, revision: &str) -> Result<Commit<'_>, Error> {
+        Ok(self.repo.revparse_single(revision)?.peel_to_commit()?)
+    }
+
+    // the commits that changed the term, the latest first - following the first parent of merges
+    pub fn term_history(&self, term_key: &str) -> Result<Vec<Revision>, Error> {
+        let mut revwalk = self.repo.revwalk()?;
+        match revwalk.push_head() {
+            Ok(()) => {}
+            Err(err) if err.code() == git2::ErrorCode::UnbornBranch => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        }
+        revwalk.simplify_first_parent()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+        let mut revisions = vec![];
+        for id in revwalk {
+            let commit = self.repo.find_commit(id?)?;
+            let files = knowledge_files(&commit.tree()?);
+            let parent_files = match commit.parent(0) {
+                Ok(parent) => knowledge_files(&parent.tree()?),
+                Err(_) => BTreeMap::new(),
+            };
+            // only the files the commit changed are read - a commit that changed none of them
+            // isn't read at all
+            let current = raw_term(&self.repo, changed_files(&files, &parent_files), term_key)?;
+            let before = raw_term(&self.repo, changed_files(&parent_files, &files), term_key)?;
+            if current != before {
+                revisions.push(Revision {
+                    id: commit.id().to_string(),
+                    time: commit.time().seconds(),
+                    summary: commit.summary().unwrap_or_default().to_owned(),
+                    is_deletion: current.is_none(),
+                });
+            }
+        }
+        Ok(revisions)
+    }
+
+    // the term as it was committed in `revision` - None when it wasn't there
+    pub fn term_at(&self, revision: &str, term_key: &str) -> Result<Option<FatTerm>, Error> {
+        let commit = self.find_commit(revision)?;
+        let files = knowledge_files(&commit.tree()?);
+        let Some(raw_term) = raw_term(&self.repo, files.into_values(), term_key)? else {
+            return Ok(None);
+        };
+        let (_, fat_term) = parse_fat_term(&raw_term)?;
+        Ok(Some(fat_term))
+    }
+}
+
+// the knowledge files at the root of the tree, by name
+fn knowledge_files(tree: &Tree<'_>) -> BTreeMap<String, Oid> {
+    tree.iter()
+        .filter(|entry| entry.kind() == Some(ObjectType::Blob))
+        .filter_map(|entry| Some((entry.name()?.to_owned(), entry.id())))
+        .filter(|(name, _)| name.ends_with(".pl"))
+        .collect()
+}
+
+// the files of `files` that `other` doesn't have as they are
+fn changed_files<'a>(
+    files: &'a BTreeMap<String, Oid>,
+    other: &'a BTreeMap<String, Oid>,
+) -> impl Iterator<Item = Oid> + 'a {
+    files
+        .iter()
+        .filter(|(name, id)| other.get(*name) != Some(*id))
+        .map(|(_, id)| *id)
+}
+
+// files that aren't utf-8 are skipped
+fn knowledge(repo: &Repository, files: impl IntoIterator<Item = Oid>) -> Result<PastTerms, Error> {
+    let mut pages = vec![];
+    for id in files {
+        let blob = repo.find_blob(id)?;
+        if let Ok(content) = std::str::from_utf8(blob.content()) {
+            pages.push(content.to_owned());
+        }
+    }
+    Ok(PastTerms::from_pages(pages.iter().map(String::as_str)))
+}
+
+// the term as it is written in the files
+fn raw_term(
+    repo: &Repository,
+    files: impl IntoIterator<Item = Oid>,
+    term_key: &str,
+) -> Result<Option<String>, Error> {
+    Ok(knowledge(repo, files)?
+        .raw_term(term_key)
+        .map(str::to_owned))
+}
+
+const SECONDS_IN_DAY: i64 = 24 * 60 * 60;
+
+// the start of a YYYY-MM-DD day in seconds since the epoch, UTC
+fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let (year, month, day): (i64, i64, i64) =
+        (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // days from the civil date - the year starts in March so that the leap day is the last one
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some((era * 146_097 + day_of_era - 719_468) * SECONDS_IN_DAY)
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+// "Change parent/2, ancestor/2; delete sibling/2" followed by the full lists
+fn message(changed: &[String], deleted: &[String]) -> String {
+    let mut summary = vec![];
+    if !changed.is_empty() {
+        summary.push(format!("Change {}", changed.join(", ")));
+    }
+    if !deleted.is_empty() {
+        let verb = if summary.is_empty() {
+            "Delete"
+        } else {
+            "delete"
+        };
+        summary.push(format!("{} {}", verb, deleted.join(", ")));
+    }
+    let mut message = summary.join("; ");
+    if message.is_empty() {
+        message.push_str("Update the knowledge");
+    }
+
+    for (title, term_keys) in [("Changed", changed), ("Deleted", deleted)] {
+        if term_keys.is_empty() {
+            continue;
+        }
+        message.push_str(&format!("\n\n{}:", title));
+        for term_key in term_keys {
+            message.push_str(&format!("\n  {}", term_key));
+        }
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::knowledge::store::{
+        persistent::Terms,
+        test_utils::{knowledge_dir, term_with_fact},
+        Delete, Keys, Put,
+    };
+
+    #[test]
+    fn test_message() {
+        assert_eq!(
+            message(&["parent/2".to_string()], &["sibling/2".to_string()]),
+            "Change parent/2; delete sibling/2\n\nChanged:\n  parent/2\n\nDeleted:\n  sibling/2"
+        );
+        assert_eq!(
+            message(&[], &["sibling/2".to_string()]),
+            "Delete sibling/2\n\nDeleted:\n  sibling/2"
+        );
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2000-03-01"), Some(951_868_800));
+        assert_eq!(parse_date("2024-02-29"), Some(1_709_164_800));
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("HEAD~1"), None);
+    }
+
+    #[test]
+    fn test_term_history() {
+        let path = knowledge_dir("history");
+        Repository::init(&path).unwrap();
+        let history = History::open(&path).unwrap().unwrap();
+
+        let mut terms = Terms::new(&path).unwrap();
+        // staged by the user - not for the history to commit
+        fs::write(path.join("notes.txt"), "not knowledge").unwrap();
+        let mut index = history.repo.index().unwrap();
+        index.add_path(Path::new("notes.txt")).unwrap();
+        index.write().unwrap();
+        terms.put("first/1", term_with_fact("first", "a")).unwrap();
+        terms
+            .put("second/1", term_with_fact("second", "a"))
+            .unwrap();
+        let created = history
+            .record(&["first/1".to_string(), "second/1".to_string()], &[])
+            .unwrap()
+            .unwrap();
+        assert_eq!(history.record(&[], &[]).unwrap(), None);
+        let committed = history.repo.head().unwrap().peel_to_tree().unwrap();
+        assert!(committed.get_name("page.pl").is_some());
+        assert!(committed.get_name("notes.txt").is_none());
+        let index = history.repo.index().unwrap();
+        assert!(index.get_path(Path::new("notes.txt"), 0).is_some());
+
+        terms.put("first/1", term_with_fact("first", "b")).unwrap();
+        history.record(&["first/1".to_string()], &[]).unwrap();
+        terms.delete("second/1").unwrap();
+        history.record(&[], &["second/1".to_string()]).unwrap();
+
+        let first_history = history.term_history("first/1").unwrap();
+        assert_eq!(first_history.len(), 2);
+        assert_eq!(first_history[0].summary, "Change first/1");
+        assert_eq!(first_history[1].id, created);
+        assert_eq!(
+            history.term_at(&created[..7], "first/1").unwrap(),
+            Some(term_with_fact("first", "a"))
+        );
+
+        let second_history = history.term_history("second/1").unwrap();
+        assert_eq!(second_history.len(), 2);
+        assert!(second_history[0].is_deletion);
+        assert_eq!(
+            history.term_at(&second_history[0].id, "second/1").unwrap(),
+            None
+        );
+
+        assert_eq!(history.find_revision("HEAD~2").unwrap(), created);
+        assert_eq!(
+            history.find_revision("9999-01-01").unwrap(),
+            second_history[0].id
+        );
+        assert!(matches!(
+            history.find_revision("1970-01-01"),
+            Err(Error::NoRevision(_))
+        ));
+        let past = history.knowledge_at(&created).unwrap();
+        assert_eq!(
+            past.keys(),
+            &vec!["first/1".to_string(), "second/1".to_string()]
+        );
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::{HashMap, HashSet};
+
+use nom::{bytes::complete::tag, Offset};
+
+use super::{
+    model::{
+        comment::{name_description::NameDescription, Comment},
+        fat_term::FatTerm,
+        parsing::{layout, ParseError},
+        term::{arg::Arg, args_binding::ArgsBinding, parse_clause, term_key, Clause, Term},
+    },
+    store::{Get, Put},
+};
+
+// Imports plain Prolog source (no "% -name description" headers) into a store. Clauses are
+// grouped by the name and arity of their head, every group becomes a FatTerm with a generated
+// comment.
+
+pub const IMPORTED_DESCRIPTION: &str = "imported - needs a description";
+
+#[derive(Debug, PartialEq)]
+pub enum ImportProblem {
+    // the clause was skipped
+    Unparsable(ParseError),
+    // ":- module(...)." and friends have no place in the model
+    Directive {
+        line: usize,
+        directive: String,
+    },
+    // the term was not imported - existing terms are never overwritten
+    AlreadyPresent(String),
+    // the term is mentioned, but neither imported nor in the store
+    UnknownTerm {
+        term_key: String,
+        mentioned_by: String,
+    },
+    // a term from the store that had to get a new @see entry couldn't be read - the store error
+    // is kept as its message
+    Unreadable(String, String),
+    // the store refused the term
+    NotWritten(String, String),
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    // terms that were already in the store and got the imported terms in their @see
+    pub updated: Vec<String>,
+    pub problems: Vec<ImportProblem>,
+}
+
+// groups the clauses of `source` into terms - nothing is written anywhere
+pub fn parse_prolog(source: &str) -> (Vec<FatTerm>, Vec<ImportProblem>) {
+    let mut terms: Vec<(String, Term)> = vec![];
+    let mut term_idx = HashMap::new();
+    let mut problems = vec![];
+
+    let mut leftover = skip_layout(source);
+    while !leftover.is_empty() {
+        let clause_start = leftover;
+
+        if tag::<_, _, nom::error::VerboseError<&str>>(":-")(leftover).is_ok() {
+            let (rest, directive) = skip_clause(leftover);
+            problems.push(ImportProblem::Directive {
+                line: ParseError::at(source, source.offset(clause_start)).line,
+                directive: directive.to_owned(),
+            });
+            leftover = skip_layout(rest);
+            continue;
+        }
+
+        let (rest, clause) = match parse_clause(leftover) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                problems.push(ImportProblem::Unparsable(ParseError::from_nom(source, err)));
+                let (rest, _) = skip_clause(leftover);
+                leftover = skip_layout(rest);
+                continue;
+            }
+        };
+        leftover = skip_layout(rest);
+
+        let (name, arity) = match &clause {
+            Clause::Fact(fact) => (fact.name.clone(), fact.arg_bindings.binding.len()),
+            Clause::Rule(name, rule) => (name.clone(), rule.head.binding.len()),
+        };
+        let idx = *term_idx.entry(term_key(&name, arity)).or_insert_with(|| {
+            terms.push((name, Term::new(&[], &[])));
+            terms.len() - 1
+        });
+        let term = &mut terms[idx].1;
+
+        match clause {
+            Clause::Fact(fact) => term.facts.push(fact.arg_bindings),
+            Clause::Rule(_, rule) => term.rules.push(rule),
+        }
+    }
+
+    let fat_terms = terms
+        .into_iter()
+        .map(|(name, term)| {
+            let args = infer_args(&term);
+            FatTerm::new(
+                Comment::new(
+                    NameDescription::new(&name, IMPORTED_DESCRIPTION),
+                    &args,
+                    &[],
+                ),
+                term,
+            )
+        })
+        .collect();
+    (fat_terms, problems)
+}
+
+// parses `source` and puts all the new terms in the store with their @see lists filled in, both
+// among the imported terms and towards the terms that were already in the store
+pub fn import(source: &str, store: &mut (impl Get + Put)) -> ImportReport {
+    let (fat_terms, problems) = parse_prolog(source);
+    let mut report = ImportReport {
+        problems,
+        ..Default::default()
+    };
+
+    let mut imported: Vec<FatTerm> = vec![];
+    for fat_term in fat_terms {
+        if matches!(store.get(&fat_term.key()), Ok(Some(_)) | Err(_)) {
+            report
+                .problems
+                .push(ImportProblem::AlreadyPresent(fat_term.key()));
+            continue;
+        }
+        imported.push(fat_term);
+    }
+
+    let imported_idx: HashMap<String, usize> = imported
+        .iter()
+        .enumerate()
+        .map(|(idx, t)| (t.key(), idx))
+        .collect();
+
+    let mut mentions = vec![];
+    for fat_term in &imported {
+        let key = fat_term.key();
+        let mut mentioned: Vec<String> = fat_term.mentioned_terms().into_iter().collect();
+        // keep the reports and @see lists stable
+        mentioned.sort();
+        for mentioned_key in mentioned {
+            if mentioned_key != key {
+                mentions.push((key.clone(), mentioned_key));
+            }
+        }
+    }
+
+    let mut updated: HashMap<String, FatTerm> = HashMap::new();
+    let mut updated_order = vec![];
+    let mut unknown = HashSet::new();
+    for (referring, mentioned_key) in mentions {
+        if let Some(&idx) = imported_idx.get(&mentioned_key) {
+            imported[idx].add_referred_by(&referring);
+            continue;
+        }
+        if let Some(existing) = updated.get_mut(&mentioned_key) {
+            existing.add_referred_by(&referring);
+            continue;
+        }
+        match store.get(&mentioned_key) {
+            Ok(Some(mut existing)) => {
+                existing.add_referred_by(&referring);
+                updated_order.push(mentioned_key.clone());
+                updated.insert(mentioned_key, existing);
+            }
+            Ok(None) => {
+                if unknown.insert(mentioned_key.clone()) {
+                    report.problems.push(ImportProblem::UnknownTerm {
+                        term_key: mentioned_key,
+                        mentioned_by: referring,
+                    });
+                }
+            }
+            Err(err) => report
+                .problems
+                .push(ImportProblem::Unreadable(mentioned_key, err.to_string())),
+        }
+    }
+
+    for fat_term in imported {
+        let key = fat_term.key();
+        match store.put(&key, fat_term) {
+            Ok(()) => report.imported.push(key),
+            Err(err) => report
+                .problems
+                .push(ImportProblem::NotWritten(key, err.to_string())),
+        }
+    }
+    for term_key in updated_order {
+        if let Some(fat_term) = updated.remove(&term_key) {
+            match store.put(&term_key, fat_term) {
+                Ok(()) => report.updated.push(term_key),
+                Err(err) => report
+                    .problems
+                    .push(ImportProblem::NotWritten(term_key, err.to_string())),
+            }
+        }
+    }
+    report
+}
+
+fn term_arity(term: &Term) -> Option<usize> {
+    term.facts
+        .first()
+        .map(|fact| fact.binding.len())
+        .or_else(|| term.rules.first().map(|rule| rule.head.binding.len()))
+}
+
+// the name of an arg is the first variable found at its position in a rule head - args that are
+// never a variable get a positional name
+fn infer_args(term: &Term) -> Vec<NameDescription> {
+    let arity = term_arity(term).unwrap_or(0);
+    let mut names: Vec<String> = vec![];
+
+    for position in 0..arity {
+        let inferred = term
+            .rules
+            .iter()
+            .filter_map(|rule| variable_name(&rule.head, position))
+            .find(|name| !names.contains(name));
+        names.push(inferred.unwrap_or_else(|| format!("Arg{}", position + 1)));
+    }
+    names
+        .iter()
+        .map(|name| NameDescription::new(name, ""))
+        .collect()
+}
+
+fn variable_name(head: &ArgsBinding, position: usize) -> Option<String> {
+    match head.binding.get(position) {
+        // "_" and "_Something" say the value doesn't matter - not much of a name
+        Some(Arg::Variable(name)) if !name.starts_with('_') => Some(name.to_owned()),
+        _ => None,
+    }
+}
+
+fn skip_layout(i: &str) -> &str {
+    match layout(i) {
+        Ok((leftover, _)) => leftover,
+        Err(_) => i,
+    }
+}
+
+// skips to right after the next "." that ends a clause - returns the leftover and the skipped part
+fn skip_clause(i: &str) -> (&str, &str) {
+    let mut chars = i.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        if c != '.' {
+            continue;
+        }
+        match chars.peek() {
+            Some((_, next)) if !next.is_whitespace() && *next != '%' => continue,
+            _ => return (&i[idx + 1..], &i[..idx + 1]),
+        }
+    }
+    ("", i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge::store::in_memory::InMemoryTerms;
+
+    const SOURCE: &str = r"
+:- module(family, [grandparent/2]).
+
+% plain prolog without headers
+parent(tom, bob).
+parent(bob, ann).
+grandparent(X, Z) :- parent(X, Y), parent(Y, Z).
+parent(tom, bob, extra).
+grandparent(X Z).
+ancestor(X, Y) :- parent(X, Y) ; relative(X, Y).
+";
+
+    #[test]
+    fn test_parse_prolog() {
+        let (terms, problems) = parse_prolog(SOURCE);
+
+        let keys: Vec<String> = terms.iter().map(|t| t.key()).collect();
+        assert_eq!(
+            keys,
+            vec!["parent/2", "grandparent/2", "parent/3", "ancestor/2"]
+        );
+        assert_eq!(terms[0].term.facts.len(), 2);
+        assert_eq!(
+            terms[1].meta.args,
+            vec![NameDescription::new("X", ""), NameDescription::new("Z", "")]
+        );
+        assert_eq!(
+            terms[0].meta.args,
+            vec![
+                NameDescription::new("Arg1", ""),
+                NameDescription::new("Arg2", "")
+            ]
+        );
+
+        assert_eq!(problems.len(), 2);
+        assert!(matches!(
+            &problems[0],
+            ImportProblem::Directive { line: 2, .. }
+        ));
+        assert!(matches!(&problems[1], ImportProblem::Unparsable(e) if e.line == 9));
+    }
+
+    #[test]
+    fn test_import() {
+        let (_, existing) = crate::knowledge::model::fat_term::parse_fat_term(
+            r"% -relative someone from the family
+% @arg Name the name
+% @arg Other the other name
+% @see
+relative(ann,tom).
+",
+        )
+        .unwrap();
+        let mut store = InMemoryTerms::new(HashMap::from([("relative/2".to_string(), existing)]));
+
+        let report = import(SOURCE, &mut store);
+
+        assert_eq!(
+            report.imported,
+            vec!["parent/2", "grandparent/2", "parent/3", "ancestor/2"]
+        );
+        assert_eq!(report.updated, vec!["relative/2"]);
+        assert_eq!(
+            store.get("parent/2").unwrap().unwrap().meta.referred_by,
+            vec!["grandparent/2".to_string(), "ancestor/2".to_string()]
+        );
+        assert_eq!(
+            store.get("relative/2").unwrap().unwrap().meta.referred_by,
+            vec!["ancestor/2".to_string()]
+        );
+        assert!(store
+            .get("parent/3")
+            .unwrap()
+            .unwrap()
+            .meta
+            .referred_by
+            .is_empty());
+
+        // importing again doesn't overwrite anything
+        let report = import(SOURCE, &mut store);
+        assert!(report.imported.is_empty());
+        assert!(report
+            .problems
+            .contains(&ImportProblem::AlreadyPresent("parent/2".to_string())));
+    }
+}
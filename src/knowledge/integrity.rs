@@ -0,0 +1,250 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use super::store::{self, Get, Keys, Transact};
+
+// The @see lists are kept up to date one change at a time. This recomputes them from what the
+// rules of every term actually mention, so that drift (a crash mid-commit, a hand edit, legacy
+// bare-name entries) can be found and fixed. A term mentioning itself is left out on both sides -
+// its own @see is no place to look for it
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Discrepancy {
+    pub term_key: String,
+    // terms that mention this one, but are not in its @see
+    pub missing: Vec<String>,
+    // @see entries that don't mention this one
+    pub stale: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct IntegrityReport {
+    pub discrepancies: Vec<Discrepancy>,
+    // what these mention is unknown, so they are never taken for stale
+    pub unreadable: Vec<(String, store::Error)>,
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.term_key)?;
+        if !self.missing.is_empty() {
+            write!(f, " not in @see - {}", self.missing.join(", "))?;
+        }
+        if !self.stale.is_empty() {
+            if !self.missing.is_empty() {
+                write!(f, ";")?;
+            }
+            write!(
+                f,
+                " in @see, but not mentioning it - {}",
+                self.stale.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl IntegrityReport {
+    pub fn is_consistent(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+pub fn check(store: &(impl Get + Keys)) -> IntegrityReport {
+    let mut report = IntegrityReport::default();
+    let mut terms = vec![];
+    for term_key in store.keys() {
+        match store.get(term_key) {
+            Ok(Some(term)) => terms.push(term),
+            Ok(None) => {}
+            Err(err) => report.unreadable.push((term_key.clone(), err)),
+        }
+    }
+    let unreadable: HashSet<&str> = report.unreadable.iter().map(|(k, _)| k.as_str()).collect();
+
+    let mut expected: HashMap<String, HashSet<String>> = HashMap::new();
+    for term in &terms {
+        let term_key = term.key();
+        for mentioned in term.mentioned_terms() {
+            if mentioned != term_key {
+                expected
+                    .entry(mentioned)
+                    .or_default()
+                    .insert(term_key.clone());
+            }
+        }
+    }
+
+    for term in &terms {
+        let term_key = term.key();
+        let expected = expected.remove(&term_key).unwrap_or_default();
+
+        let mut missing: Vec<String> = expected
+            .iter()
+            .filter(|k| !term.meta.referred_by.contains(k))
+            .cloned()
+            .collect();
+        missing.sort();
+        let stale: Vec<String> = term
+            .meta
+            .referred_by
+            .iter()
+            .filter(|k| **k != term_key && !expected.contains(*k))
+            .filter(|k| !unreadable.contains(k.as_str()))
+            .cloned()
+            .collect();
+
+        if !missing.is_empty() || !stale.is_empty() {
+            report.discrepancies.push(Discrepancy {
+                term_key,
+                missing,
+                stale,
+            });
+        }
+    }
+    report
+        .discrepancies
+        .sort_by(|a, b| a.term_key.cmp(&b.term_key));
+    report
+}
+
+// rewrites the @see lists of the terms in the report - the entries that stay keep their order,
+// the missing ones go at the end. Returns the keys of the terms that were written. They are
+// written in a single commit, so on an error - a term that can't be read included - none is
+pub fn repair(
+    store: &mut (impl Get + Transact),
+    report: &IntegrityReport,
+) -> Result<Vec<String>, store::Error> {
+    let mut repaired = vec![];
+    let mut transaction = store.begin();
+    for discrepancy in &report.discrepancies {
+        // deleted since the check - there is no @see left to repair
+        let Some(mut term) = store.get(&discrepancy.term_key)? else {
+            continue;
+        };
+        term.meta
+            .referred_by
+            .retain(|k| !discrepancy.stale.contains(k));
+        for missing in &discrepancy.missing {
+            term.add_referred_by(missing);
+        }
+        transaction.put(&discrepancy.term_key, term);
+        repaired.push(discrepancy.term_key.clone());
+    }
+    store.commit(transaction)?;
+    Ok(repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge::{
+        model::fat_term::{parse_fat_term, FatTerm},
+        store::in_memory::InMemoryTerms,
+    };
+
+    fn parse(input: &str) -> FatTerm {
+        parse_fat_term(input).unwrap().1
+    }
+
+    #[test]
+    fn test_check_and_repair() {
+        let parent = parse(
+            r"% -parent a parent of someone
+% @arg Parent the parent
+% @arg Child the child
+% @see ancestor/2,grandparent,sibling/2
+parent(tom,bob).
+",
+        );
+        let grandparent = parse(
+            r"% -grandparent a parent of a parent
+% @arg X the grandparent
+% @arg Z the grandchild
+% @see
+grandparent(X,Z):-parent(X,Y),parent(Y,Z).
+",
+        );
+        let ancestor = parse(
+            r"% -ancestor a parent or an ancestor of a parent
+% @arg X the ancestor
+% @arg Z the descendant
+% @see ancestor/2
+ancestor(X,Z):-parent(X,Z).
+ancestor(X,Z):-parent(X,Y),ancestor(Y,Z).
+",
+        );
+        let mut store = InMemoryTerms::new(HashMap::from([
+            (parent.key(), parent),
+            (grandparent.key(), grandparent),
+            (ancestor.key(), ancestor),
+        ]));
+
+        let report = check(&store);
+        assert_eq!(
+            report.discrepancies,
+            vec![Discrepancy {
+                term_key: "parent/2".to_string(),
+                missing: vec!["grandparent/2".to_string()],
+                stale: vec!["grandparent".to_string(), "sibling/2".to_string()],
+            }]
+        );
+
+        assert_eq!(repair(&mut store, &report).unwrap(), vec!["parent/2"]);
+        assert_eq!(
+            store.get("parent/2").unwrap().unwrap().meta.referred_by,
+            vec!["ancestor/2".to_string(), "grandparent/2".to_string()]
+        );
+        assert!(check(&store).is_consistent());
+    }
+
+    // reads every term but the one it's told can't be read
+    struct PartlyUnreadable(InMemoryTerms, &'static str);
+
+    impl Get for PartlyUnreadable {
+        fn get(&self, term_key: &str) -> Result<Option<FatTerm>, store::Error> {
+            if term_key == self.1 {
+                return Err(store::Error::Engine("unreadable".to_string()));
+            }
+            self.0.get(term_key)
+        }
+    }
+
+    impl Transact for PartlyUnreadable {
+        fn commit(&mut self, transaction: store::Transaction) -> Result<(), store::Error> {
+            self.0.commit(transaction)
+        }
+    }
+
+    #[test]
+    fn test_repair_writes_nothing_when_a_term_is_unreadable() {
+        let first = parse("% -first\n% @see stale/1\nfirst.\n");
+        let second = parse("% -second\n% @see stale/1\nsecond.\n");
+        let mut store = PartlyUnreadable(
+            InMemoryTerms::new(HashMap::from([
+                (first.key(), first.clone()),
+                (second.key(), second),
+            ])),
+            "second/0",
+        );
+        let report = IntegrityReport {
+            discrepancies: ["first/0", "second/0"]
+                .into_iter()
+                .map(|term_key| Discrepancy {
+                    term_key: term_key.to_string(),
+                    missing: vec![],
+                    stale: vec!["stale/1".to_string()],
+                })
+                .collect(),
+            unreadable: vec![],
+        };
+
+        assert!(matches!(
+            repair(&mut store, &report),
+            Err(store::Error::Engine(_))
+        ));
+        assert_eq!(store.get("first/0").unwrap(), Some(first));
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use super::{
+    model::{
+        fat_term::FatTerm,
+        term::{rule::Rule, split_term_key},
+    },
+    store::{Get, Keys},
+};
+
+// Static checks over the rules and facts of terms - nothing is consulted, only the model and the
+// keys in the store are looked at
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Severity {
+    // the term works, but likely not the way it was meant to
+    Warning,
+    // the term can't work as written
+    Error,
+}
+
+// rule and fact indexes are 0-based, they are shown 1-based
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum Lint {
+    #[error("rule {}: {variable} appears only once", .rule_idx + 1)]
+    SingletonVariable { rule_idx: usize, variable: String },
+    #[error("rule {}: {variable} is in the head, but never in the body", .rule_idx + 1)]
+    HeadVariableNotInBody { rule_idx: usize, variable: String },
+    #[error("rule {}: {name} is called with {found} args, but takes {}", .rule_idx + 1, join_arities(.declared))]
+    WrongArgCount {
+        rule_idx: usize,
+        name: String,
+        found: usize,
+        declared: Vec<usize>,
+    },
+    #[error("rule {}: {term_key} doesn't exist", .rule_idx + 1)]
+    MissingTerm { rule_idx: usize, term_key: String },
+    #[error("fact {}: has {found} args, but {declared} are declared", .fact_idx + 1)]
+    FactArityMismatch {
+        fact_idx: usize,
+        found: usize,
+        declared: usize,
+    },
+    // the store error is kept as its message
+    #[error("can't be read: {0}")]
+    Unreadable(String),
+}
+
+#[derive(Error, Debug, PartialEq, Clone)]
+#[error("{term_key}: {lint}")]
+pub struct Diagnostic {
+    pub term_key: String,
+    pub lint: Lint,
+}
+
+impl Lint {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Lint::SingletonVariable { .. } | Lint::HeadVariableNotInBody { .. } => {
+                Severity::Warning
+            }
+            Lint::WrongArgCount { .. }
+            | Lint::MissingTerm { .. }
+            | Lint::FactArityMismatch { .. }
+            | Lint::Unreadable(_) => Severity::Error,
+        }
+    }
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        self.lint.severity()
+    }
+}
+
+// the keys of `store` tell which calls go to existing terms - the term itself doesn't have to be
+// in the store yet
+pub fn lint_term(term: &FatTerm, store: &impl Keys) -> Vec<Diagnostic> {
+    let term_key = term.key();
+    let known_keys: HashSet<&str> = store
+        .keys()
+        .iter()
+        .map(String::as_str)
+        .chain([term_key.as_str()])
+        .collect();
+
+    let mut lints = vec![];
+    for (rule_idx, rule) in term.term.rules.iter().enumerate() {
+        lints.extend(lint_variables(rule_idx, rule));
+        lints.extend(lint_calls(rule_idx, rule, &known_keys));
+    }
+    let declared = term.meta.args.len();
+    for (fact_idx, fact) in term.term.facts.iter().enumerate() {
+        if fact.binding.len() != declared {
+            lints.push(Lint::FactArityMismatch {
+                fact_idx,
+                found: fact.binding.len(),
+                declared,
+            });
+        }
+    }
+    lints
+        .into_iter()
+        .map(|lint| Diagnostic {
+            term_key: term_key.clone(),
+            lint,
+        })
+        .collect()
+}
+
+// every term in the store - the ones that can't be parsed are reported as well
+pub fn lint_store(store: &(impl Get + Keys)) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for term_key in store.keys() {
+        match store.get(term_key) {
+            Ok(Some(term)) => diagnostics.extend(lint_term(&term, store)),
+            Ok(None) => {}
+            Err(err) => diagnostics.push(Diagnostic {
+                term_key: term_key.clone(),
+                lint: Lint::Unreadable(err.to_string()),
+            }),
+        }
+    }
+    diagnostics
+}
+
+// variables starting with "_" are meant to be ignored
+fn lint_variables(rule_idx: usize, rule: &Rule) -> Vec<Lint> {
+    let head: Vec<&str> = rule.head.variables();
+    let body: Vec<&str> = rule.body.iter().flat_map(|g| g.variables()).collect();
+
+    let mut lints = vec![];
+    let mut seen = HashSet::new();
+    for variable in head.iter().filter(|v| !v.starts_with('_')) {
+        if seen.insert(*variable) && !body.contains(variable) {
+            lints.push(Lint::HeadVariableNotInBody {
+                rule_idx,
+                variable: variable.to_string(),
+            });
+        }
+    }
+    for variable in body.iter().filter(|v| !v.starts_with('_')) {
+        let occurrences = body.iter().filter(|v| *v == variable).count();
+        if occurrences == 1 && !head.contains(variable) {
+            lints.push(Lint::SingletonVariable {
+                rule_idx,
+                variable: variable.to_string(),
+            });
+        }
+    }
+    lints
+}
+
+fn lint_calls(rule_idx: usize, rule: &Rule, known_keys: &HashSet<&str>) -> Vec<Lint> {
+    let mut lints = vec![];
+    let mut reported = HashSet::new();
+    for body_term in rule.body_terms() {
+        let called_key = body_term.key();
+        if known_keys.contains(called_key.as_str()) || !reported.insert(called_key.clone()) {
+            continue;
+        }
+        let mut declared: Vec<usize> = known_keys
+            .iter()
+            .filter_map(|key| split_term_key(key))
+            .filter(|(name, _)| *name == body_term.name)
+            .map(|(_, arity)| arity)
+            .collect();
+        declared.sort();
+
+        if declared.is_empty() {
+            lints.push(Lint::MissingTerm {
+                rule_idx,
+                term_key: called_key,
+            });
+        } else {
+            lints.push(Lint::WrongArgCount {
+                rule_idx,
+                name: body_term.name.clone(),
+                found: body_term.arg_bindings.binding.len(),
+                declared,
+            });
+        }
+    }
+    lints
+}
+
+fn join_arities(arities: &[usize]) -> String {
+    let arities: Vec<String> = arities.iter().map(|a| a.to_string()).collect();
+    arities.join(" or ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::knowledge::{model::fat_term::parse_fat_term, store::in_memory::InMemoryTerms};
+
+    fn parse(input: &str) -> FatTerm {
+        parse_fat_term(input).unwrap().1
+    }
+
+    #[test]
+    fn test_lint_store() {
+        let parent = parse(
+            r"% -parent a parent of someone
+% @arg Parent the parent
+% @arg Child the child
+% @see grandparent/2
+parent(tom,bob).
+parent(bob).
+",
+        );
+        let grandparent = parse(
+            r"% -grandparent a parent of a parent
+% @arg Grandparent the grandparent
+% @arg Grandchild the grandchild
+% @see
+grandparent(X,Z):-parent(X,Y),parent(Y,Z).
+grandparent(X,Z):-parent(X,Y,_),sibling(Y,W),Z>0.
+grandparent(X,_Z):-parent(X,[_|T]),T=[].
+",
+        );
+        let store = InMemoryTerms::new(HashMap::from([
+            (parent.key(), parent),
+            (grandparent.key(), grandparent),
+        ]));
+
+        let mut diagnostics = lint_store(&store);
+        diagnostics.sort_by_key(|d| d.term_key.clone());
+        let lints: Vec<Lint> = diagnostics.into_iter().map(|d| d.lint).collect();
+        assert_eq!(
+            lints,
+            vec![
+                Lint::SingletonVariable {
+                    rule_idx: 1,
+                    variable: "W".to_string(),
+                },
+                Lint::WrongArgCount {
+                    rule_idx: 1,
+                    name: "parent".to_string(),
+                    found: 3,
+                    declared: vec![2],
+                },
+                Lint::MissingTerm {
+                    rule_idx: 1,
+                    term_key: "sibling/2".to_string(),
+                },
+                Lint::FactArityMismatch {
+                    fact_idx: 1,
+                    found: 1,
+                    declared: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_variables() {
+        let (_, rule) =
+            crate::knowledge::model::term::rule::parse_rule("a(X,Y,Y,_Ignored):-b(Z),\\+c(Z,_,W).")
+                .unwrap();
+        assert_eq!(
+            lint_variables(0, &rule),
+            vec![
+                Lint::HeadVariableNotInBody {
+                    rule_idx: 0,
+                    variable: "X".to_string(),
+                },
+                Lint::HeadVariableNotInBody {
+                    rule_idx: 0,
+                    variable: "Y".to_string(),
+                },
+                Lint::SingletonVariable {
+                    rule_idx: 0,
+                    variable: "W".to_string(),
+                },
+            ]
+        );
+    }
+}
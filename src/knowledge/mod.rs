@@ -1,2 +1,7 @@
+pub mod history;
+pub mod import;
+pub mod integrity;
+pub mod lint;
 pub mod model;
 pub mod store;
+pub mod type_check;
@@ -0,0 +1,190 @@
+use std::fmt;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, digit1, satisfy},
+    combinator::{map, map_res, not, value},
+    error::VerboseError,
+    multi::separated_list1,
+    sequence::{delimited, separated_pair, terminated},
+    IResult,
+};
+
+use crate::knowledge::model::term::{
+    arg::{encode_atom, parse_atom_name, Arg},
+    term_key,
+};
+
+// The type of an argument as declared in "% @arg Age:integer the age". Values that are variables
+// fit any type - "_" in a fact means that the value doesn't matter
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+pub enum ArgType {
+    Atom,
+    Integer,
+    // integers are accepted as well - "3" is a fine float for a person to type
+    Float,
+    // "2024-02-29" - as a quoted atom or a string
+    Date,
+    Enum(Vec<String>),
+    // the value has to be the first argument of one of the facts of the referenced term - the
+    // "name/arity" key of the term is kept
+    Reference(String),
+}
+
+impl ArgType {
+    pub fn encode(&self) -> String {
+        match self {
+            ArgType::Atom => "atom".to_string(),
+            ArgType::Integer => "integer".to_string(),
+            ArgType::Float => "float".to_string(),
+            ArgType::Date => "date".to_string(),
+            ArgType::Enum(values) => {
+                let values: Vec<String> = values.iter().map(|v| encode_atom(v)).collect();
+                format!("enum({})", values.join("|"))
+            }
+            ArgType::Reference(term_key) => format!("ref({})", term_key),
+        }
+    }
+
+    // whether it can be written and read back - an enum needs at least one value and a reference
+    // a "name/arity" key. "enum()" or "ref(foo)" would make the whole term unreadable
+    pub fn is_complete(&self) -> bool {
+        matches!(parse_arg_type(&self.encode()), Ok(("", parsed)) if parsed == *self)
+    }
+
+    // whether the value has the right shape - references also need the referenced term, which is
+    // up to the type_check module
+    pub fn accepts(&self, arg: &Arg) -> bool {
+        match (self, arg) {
+            (_, Arg::Variable(_)) => true,
+            (ArgType::Atom, Arg::Atom(_)) => true,
+            (ArgType::Integer, Arg::Integer(_)) => true,
+            (ArgType::Float, Arg::Float(_) | Arg::Integer(_)) => true,
+            (ArgType::Date, Arg::Atom(date) | Arg::String(date)) => is_date(date),
+            (ArgType::Enum(values), Arg::Atom(value)) => values.contains(value),
+            (ArgType::Reference(_), Arg::Atom(_) | Arg::Integer(_) | Arg::String(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ArgType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.encode())
+    }
+}
+
+// parses "integer", "enum(red|green)", "ref(person/1)", ...
+pub fn parse_arg_type(i: &str) -> IResult<&str, ArgType, VerboseError<&str>> {
+    alt((
+        map(
+            delimited(
+                tag("enum("),
+                separated_list1(char('|'), parse_atom_name),
+                char(')'),
+            ),
+            ArgType::Enum,
+        ),
+        map(
+            delimited(
+                tag("ref("),
+                separated_pair(parse_atom_name, char('/'), map_res(digit1, str::parse)),
+                char(')'),
+            ),
+            |(name, arity)| ArgType::Reference(term_key(&name, arity)),
+        ),
+        // "atomic" is not "atom"
+        terminated(
+            alt((
+                value(ArgType::Atom, tag("atom")),
+                value(ArgType::Integer, tag("integer")),
+                value(ArgType::Float, tag("float")),
+                value(ArgType::Date, tag("date")),
+            )),
+            not(satisfy(|c| c.is_alphanumeric() || c == '_')),
+        ),
+    ))(i)
+}
+
+// YYYY-MM-DD with the month and the day in range
+fn is_date(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return false;
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return false;
+    }
+    let (Ok(year), Ok(month), Ok(day)) = (
+        year.parse::<u32>(),
+        month.parse::<u32>(),
+        day.parse::<u32>(),
+    ) else {
+        return false;
+    };
+    let is_leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days_in_month).contains(&day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge::model::term::arg::Float;
+
+    #[test]
+    fn test_parse_encode_arg_types() {
+        let cases = [
+            ("atom", ArgType::Atom),
+            ("integer", ArgType::Integer),
+            ("float", ArgType::Float),
+            ("date", ArgType::Date),
+            (
+                "enum(red|'light green')",
+                ArgType::Enum(vec!["red".to_string(), "light green".to_string()]),
+            ),
+            ("ref(person/1)", ArgType::Reference("person/1".to_string())),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_arg_type(input), Ok(("", expected.clone())));
+            assert_eq!(expected.encode(), input);
+        }
+        assert!(parse_arg_type("atomic").is_err());
+    }
+
+    #[test]
+    fn test_is_complete() {
+        assert!(ArgType::Integer.is_complete());
+        assert!(ArgType::Enum(vec!["red".to_string(), "light green".to_string()]).is_complete());
+        assert!(ArgType::Reference("person/1".to_string()).is_complete());
+
+        assert!(!ArgType::Enum(vec![]).is_complete());
+        assert!(!ArgType::Reference(String::new()).is_complete());
+        assert!(!ArgType::Reference("person".to_string()).is_complete());
+        assert!(!ArgType::Reference("person/one".to_string()).is_complete());
+    }
+
+    #[test]
+    fn test_accepts() {
+        assert!(ArgType::Integer.accepts(&Arg::Integer(4)));
+        assert!(ArgType::Integer.accepts(&Arg::Variable("_".to_string())));
+        assert!(!ArgType::Integer.accepts(&Arg::Float(Float(4.5))));
+        assert!(ArgType::Float.accepts(&Arg::Integer(4)));
+        assert!(!ArgType::Atom.accepts(&Arg::String("bob".to_string())));
+
+        assert!(ArgType::Date.accepts(&Arg::Atom("2024-02-29".to_string())));
+        assert!(!ArgType::Date.accepts(&Arg::Atom("2023-02-29".to_string())));
+        assert!(!ArgType::Date.accepts(&Arg::String("2023-13-01".to_string())));
+
+        let colors = ArgType::Enum(vec!["red".to_string(), "green".to_string()]);
+        assert!(colors.accepts(&Arg::Atom("red".to_string())));
+        assert!(!colors.accepts(&Arg::Atom("blue".to_string())));
+    }
+}
@@ -0,0 +1,122 @@
+use nom::{
+    branch::alt,
+    bytes::complete::take_while1,
+    character::complete::{char, line_ending, not_line_ending, space0},
+    combinator::{eof, verify},
+    error::VerboseError,
+    sequence::{preceded, terminated, tuple},
+    IResult,
+};
+
+use crate::knowledge::model::term::split_term_key;
+
+pub const EXAMPLE: &str = "example";
+pub const TAG: &str = "tag";
+pub const AUTHOR: &str = "author";
+pub const SINCE: &str = "since";
+pub const DEPRECATED: &str = "deprecated";
+
+// "@arg" and "@see" have their own place in the Comment
+const RESERVED: [&str; 2] = ["arg", "see"];
+
+// A "% @name value" line in the comment of a term. Tags that nothing knows about are kept as they
+// are, so that a round-trip through the app doesn't lose them
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DocTag {
+    pub name: String,
+    pub value: String,
+}
+
+// "% @deprecated ancestor/2 it was too narrow" - the replacement is there only when the first
+// word is a "name/arity" key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deprecation {
+    pub replacement: Option<String>,
+    pub reason: String,
+}
+
+impl DocTag {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    // without the leading "% "
+    pub fn encode(&self) -> String {
+        if self.value.is_empty() {
+            return format!("@{}", self.name);
+        }
+        format!("@{} {}", self.name, self.value)
+    }
+}
+
+impl Deprecation {
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        let (first_word, rest) = value.split_once(' ').unwrap_or((value, ""));
+        if split_term_key(first_word).is_some() {
+            return Self {
+                replacement: Some(first_word.to_string()),
+                reason: rest.trim().to_string(),
+            };
+        }
+        Self {
+            replacement: None,
+            reason: value.to_string(),
+        }
+    }
+}
+
+// parses "% @name some value" up to the end of the line - "@arg" and "@see" are not doc tags
+pub fn parse_doc_tag(i: &str) -> IResult<&str, DocTag, VerboseError<&str>> {
+    preceded(
+        tuple((char('%'), space0, char('@'))),
+        tuple((
+            verify(
+                take_while1(|c: char| c.is_alphanumeric() || c == '_' || c == '-'),
+                |name: &str| !RESERVED.contains(&name),
+            ),
+            preceded(space0, terminated(not_line_ending, alt((line_ending, eof)))),
+        )),
+    )(i)
+    .map(|(leftover, (name, value))| (leftover, DocTag::new(name, value.trim_end())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_encode_doc_tag() {
+        let (leftover, doc_tag) = parse_doc_tag("% @example parent(tom, X).\n").unwrap();
+        assert_eq!(leftover, "");
+        assert_eq!(doc_tag, DocTag::new(EXAMPLE, "parent(tom, X)."));
+        assert_eq!(doc_tag.encode(), "@example parent(tom, X).");
+
+        let (_, doc_tag) = parse_doc_tag("%@reviewed").unwrap();
+        assert_eq!(doc_tag.encode(), "@reviewed");
+
+        assert!(parse_doc_tag("% @see parent/2\n").is_err());
+        assert!(parse_doc_tag("% @arg Name the name\n").is_err());
+    }
+
+    #[test]
+    fn test_deprecation() {
+        assert_eq!(
+            Deprecation::parse("ancestor/2 covers more cases"),
+            Deprecation {
+                replacement: Some("ancestor/2".to_string()),
+                reason: "covers more cases".to_string(),
+            }
+        );
+        assert_eq!(
+            Deprecation::parse("nobody uses it"),
+            Deprecation {
+                replacement: None,
+                reason: "nobody uses it".to_string(),
+            }
+        );
+    }
+}
@@ -1,30 +1,51 @@
 use nom::{
-    bytes::complete::{tag, take_till1, take_until},
-    error::VerboseError,
+    branch::alt,
+    bytes::complete::{tag, take_till1},
+    character::complete::{char, digit1, line_ending, space0, space1},
+    combinator::{eof, map, map_res, opt},
+    error::{context, VerboseError},
     multi::{many0, separated_list0},
-    sequence::{preceded, terminated, tuple},
+    sequence::{delimited, pair, preceded, terminated, tuple},
     IResult,
 };
 
+use super::parsing::{clause_layout, term_header_start};
+use super::term::{
+    arg::{encode_atom, parse_quoted_atom},
+    split_term_key, term_key,
+};
+
+pub mod arg_type;
+pub mod doc_tag;
 pub mod name_description;
 
-use name_description::{parse_name_description, NameDescription};
+use doc_tag::{parse_doc_tag, Deprecation, DocTag};
+use name_description::{parse_arg_description, parse_name_description, NameDescription};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Comment {
     pub term: NameDescription,
     pub args: Vec<NameDescription>,
+    // everything between the header and "@see" that isn't an "@arg" - in the order it was written
+    pub doc_tags: Vec<DocTag>,
     pub referred_by: Vec<String>,
 }
 
+// the lines that may come between the header and "@see", in any order
+enum CommentLine {
+    Arg(NameDescription),
+    DocTag(DocTag),
+}
+
 const NEWLINE: &str = r"
 ";
+const TERM_HEADER_START: &str = "% -";
 
 impl Comment {
     pub fn encode(&self) -> String {
-        let term_encoded = self.term.encode();
-        let mut encoded = String::with_capacity(term_encoded.len() + "% -".len() + 1);
-        encoded.push_str("% -");
+        let term_encoded = self.term.encode_term();
+        let mut encoded = String::with_capacity(term_encoded.len() + TERM_HEADER_START.len() + 1);
+        encoded.push_str(TERM_HEADER_START);
         encoded.push_str(&term_encoded);
         encoded.push_str(NEWLINE);
 
@@ -34,8 +55,15 @@ impl Comment {
             encoded.push_str(NEWLINE);
         }
 
+        for doc_tag in &self.doc_tags {
+            encoded.push_str("% ");
+            encoded.push_str(&doc_tag.encode());
+            encoded.push_str(NEWLINE);
+        }
+
         encoded.push_str("% @see ");
-        encoded.push_str(&self.referred_by.join(","));
+        let referred_by: Vec<String> = self.referred_by.iter().map(|k| encode_key(k)).collect();
+        encoded.push_str(&referred_by.join(","));
         encoded.push_str(NEWLINE);
         encoded
     }
@@ -44,53 +72,139 @@ impl Comment {
         Self {
             term,
             args: args.to_vec(),
+            doc_tags: vec![],
             referred_by: referred_by.to_vec(),
         }
     }
+
+    pub fn with_doc_tag(mut self, name: &str, value: &str) -> Self {
+        self.doc_tags.push(DocTag::new(name, value));
+        self
+    }
+
+    fn doc_tag_values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.doc_tags
+            .iter()
+            .filter(move |doc_tag| doc_tag.name == name)
+            .map(|doc_tag| doc_tag.value.as_str())
+    }
+
+    // one "@example" per example
+    pub fn examples(&self) -> Vec<&str> {
+        self.doc_tag_values(doc_tag::EXAMPLE).collect()
+    }
+
+    // "@tag family, genealogy" - any number of "@tag" lines with comma separated tags
+    pub fn tags(&self) -> Vec<&str> {
+        self.doc_tag_values(doc_tag::TAG)
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    // the first one wins for the single valued tags
+    pub fn author(&self) -> Option<&str> {
+        self.doc_tag_values(doc_tag::AUTHOR).next()
+    }
+
+    pub fn since(&self) -> Option<&str> {
+        self.doc_tag_values(doc_tag::SINCE).next()
+    }
+
+    pub fn deprecated(&self) -> Option<Deprecation> {
+        self.doc_tag_values(doc_tag::DEPRECATED)
+            .next()
+            .map(Deprecation::parse)
+    }
 }
 
 pub fn parse_comment(i: &str) -> IResult<&str, Comment, VerboseError<&str>> {
-    take_until("% -")(i)
-        .and_then(|(leftover, _)| {
-            tuple((
-                term_definition_parser,
-                args_definition_parser,
-                referred_by_terms_parser,
-            ))(leftover)
-        })
-        .map(|(leftover, (term, args, referred_by))| {
-            (
-                leftover,
-                Comment {
-                    term,
-                    args,
-                    referred_by,
-                },
-            )
-        })
+    preceded(
+        clause_layout,
+        tuple((
+            term_definition_parser,
+            many0(alt((
+                map(arg_definition_parser, CommentLine::Arg),
+                map(parse_doc_tag, CommentLine::DocTag),
+            ))),
+            referred_by_terms_parser,
+        )),
+    )(i)
+    .map(|(leftover, (term, lines, referred_by))| {
+        let mut args = vec![];
+        let mut doc_tags = vec![];
+        for line in lines {
+            match line {
+                CommentLine::Arg(arg) => args.push(arg),
+                CommentLine::DocTag(doc_tag) => doc_tags.push(doc_tag),
+            }
+        }
+        (
+            leftover,
+            Comment {
+                term,
+                args,
+                doc_tags,
+                referred_by,
+            },
+        )
+    })
 }
 
 fn term_definition_parser(i: &str) -> IResult<&str, NameDescription, VerboseError<&str>> {
-    preceded(tag("% -"), parse_name_description)(i)
+    preceded(
+        context("'% -name description' header", term_header_start),
+        parse_name_description,
+    )(i)
 }
 
-fn args_definition_parser(i: &str) -> IResult<&str, Vec<NameDescription>, VerboseError<&str>> {
-    many0(preceded(tag("% @arg "), parse_name_description))(i)
+fn arg_definition_parser(i: &str) -> IResult<&str, NameDescription, VerboseError<&str>> {
+    preceded(
+        tuple((char('%'), space0, tag("@arg"), space1)),
+        parse_arg_description,
+    )(i)
 }
 
 fn referred_by_terms_parser(i: &str) -> IResult<&str, Vec<String>, VerboseError<&str>> {
     preceded(
-        tag("% @see "),
+        context(
+            "'% @see' line",
+            tuple((char('%'), space0, tag("@see"), space0)),
+        ),
         terminated(
-            separated_list0(tag(","), parse_to_owned_string),
-            nom::character::complete::char('\n'),
+            separated_list0(delimited(space0, char(','), space0), parse_key),
+            tuple((space0, alt((line_ending, eof)))),
         ),
     )(i)
 }
 
-fn parse_to_owned_string(i: &str) -> IResult<&str, String, VerboseError<&str>> {
-    take_till1(|c| c == ',' || c == '\n')(i)
-        .map(|(leftover, parsed)| (leftover, parsed.to_string()))
+// "name/arity" with the name quoted like an atom - a plain name is written as it is
+fn encode_key(key: &str) -> String {
+    match split_term_key(key) {
+        Some((name, arity)) => term_key(&encode_atom(name), arity),
+        None => encode_atom(key),
+    }
+}
+
+// "'lives in'/2" or - as written before names were quoted - anything up to a comma or whitespace
+fn parse_key(i: &str) -> IResult<&str, String, VerboseError<&str>> {
+    alt((
+        map(
+            pair(
+                parse_quoted_atom,
+                opt(preceded(char('/'), map_res(digit1, str::parse::<usize>))),
+            ),
+            |(name, arity)| match arity {
+                Some(arity) => term_key(&name, arity),
+                None => name,
+            },
+        ),
+        map(
+            take_till1(|c: char| c == ',' || c.is_whitespace()),
+            str::to_owned,
+        ),
+    ))(i)
 }
 
 #[test]
@@ -114,3 +228,60 @@ fn test_referred_by_terms_parser() {
     );
     assert_eq!(res, Ok(("", vec![])));
 }
+
+#[test]
+fn test_parse_encode_doc_tags() {
+    let input = r"% -parent a parent of someone
+% @arg Parent the parent
+% @example parent(tom, X).
+% @arg Child the child
+% @tag family, genealogy
+% @deprecated ancestor/2 it's a special case
+% @reviewed by the family
+% @see grandparent/2
+";
+    let (leftover, comment) = parse_comment(input).unwrap();
+    assert_eq!(leftover, "");
+    assert_eq!(comment.args.len(), 2);
+    assert_eq!(comment.examples(), vec!["parent(tom, X)."]);
+    assert_eq!(comment.tags(), vec!["family", "genealogy"]);
+    assert_eq!(comment.author(), None);
+    assert_eq!(
+        comment.deprecated().and_then(|d| d.replacement),
+        Some("ancestor/2".to_string())
+    );
+    assert!(comment
+        .doc_tags
+        .contains(&DocTag::new("reviewed", "by the family")));
+
+    // the args come first, the doc tags keep their order
+    let encoded = comment.encode();
+    assert_eq!(
+        encoded,
+        r"% -parent a parent of someone
+% @arg Parent the parent
+% @arg Child the child
+% @example parent(tom, X).
+% @tag family, genealogy
+% @deprecated ancestor/2 it's a special case
+% @reviewed by the family
+% @see grandparent/2
+"
+    );
+    assert_eq!(parse_comment(&encoded).unwrap().1, comment);
+}
+
+#[test]
+fn test_parse_encode_quoted_names() {
+    let comment = Comment::new(
+        NameDescription::new("lives in", "where someone lives"),
+        &[NameDescription::new("Person", "")],
+        &["moved to/2".to_string(), "home, sweet/1".to_string()],
+    );
+    let encoded = comment.encode();
+    assert_eq!(
+        encoded,
+        "% -'lives in' where someone lives\n% @arg Person \n% @see 'moved to'/2,'home, sweet'/1\n"
+    );
+    assert_eq!(parse_comment(&encoded), Ok(("", comment)));
+}
@@ -1,14 +1,24 @@
 use std::fmt;
 
-use nom::bytes::complete::{tag, take_until};
-use nom::error::VerboseError;
-use nom::sequence::{separated_pair, terminated};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::bytes::complete::take_till1;
+use nom::character::complete::{char, line_ending, not_line_ending, space0};
+use nom::combinator::{cut, eof, map, opt, value};
+use nom::error::{context, VerboseError};
+use nom::multi::many0;
+use nom::sequence::{pair, preceded, separated_pair, terminated, tuple};
 use nom::IResult;
 
+use super::arg_type::{parse_arg_type, ArgType};
+use crate::knowledge::model::term::arg::{encode_atom, parse_quoted_atom};
+
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub struct NameDescription {
     pub name: String,
     pub desc: String,
+    // only args have a type and it's optional for them too
+    pub arg_type: Option<ArgType>,
 }
 
 impl NameDescription {
@@ -16,38 +26,167 @@ impl NameDescription {
         Self {
             name: name.to_string(),
             desc: desc.to_string(),
+            arg_type: None,
         }
     }
 
+    pub fn with_arg_type(mut self, arg_type: ArgType) -> Self {
+        self.arg_type = Some(arg_type);
+        self
+    }
+
     pub fn encode(&self) -> String {
-        let mut encoded = String::with_capacity(self.name.len() + 1 + self.desc.len());
+        self.encode_named(&self.name)
+    }
+
+    // the term header - the name is quoted the way an atom is, so that a name with a space or a
+    // comma is read back whole
+    pub fn encode_term(&self) -> String {
+        self.encode_named(&encode_atom(&self.name))
+    }
+
+    fn encode_named(&self, name: &str) -> String {
+        let mut encoded = String::with_capacity(name.len() + 1 + self.desc.len());
 
-        encoded.push_str(&self.name);
+        encoded.push_str(name);
+        if let Some(arg_type) = &self.arg_type {
+            encoded.push(':');
+            encoded.push_str(&arg_type.encode());
+        }
         encoded.push(' ');
-        encoded.push_str(&self.desc);
+        for (idx, line) in self.desc.lines().enumerate() {
+            if idx > 0 {
+                encoded.push('\n');
+                encoded.push_str(if line.is_empty() { "%" } else { CONTINUATION });
+            }
+            encoded.push_str(line);
+        }
         encoded
     }
 }
 
+// the lines after the first one of a description start with "%   " - a lone "%" is an empty line
+// that separates paragraphs
+const CONTINUATION: &str = "%   ";
+
 impl fmt::Display for NameDescription {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}| {}", self.name, self.desc)
     }
 }
 
+// parses "name some description" or "'some name' some description" up to the end of the line -
+// the description may be missing
 pub fn parse_name_description(i: &str) -> IResult<&str, NameDescription, VerboseError<&str>> {
-    separated_pair(
-        take_until(" "),
-        tag(" "),
-        terminated(take_until("\n"), nom::character::complete::char('\n')),
+    separated_pair(parse_term_name, space0, parse_description)(i)
+        .map(|(leftover, (name, desc))| (leftover, NameDescription::new(&name, &desc)))
+}
+
+// a quoted atom, or - as written before names were quoted - anything up to the first whitespace
+pub(crate) fn parse_term_name(i: &str) -> IResult<&str, String, VerboseError<&str>> {
+    alt((
+        parse_quoted_atom,
+        map(take_till1(char::is_whitespace), str::to_owned),
+    ))(i)
+}
+
+// parses "Name:type some description" - the ":type" part is optional, but once there it has to be
+// a known type
+pub fn parse_arg_description(i: &str) -> IResult<&str, NameDescription, VerboseError<&str>> {
+    tuple((
+        take_till1(|c: char| c.is_whitespace() || c == ':'),
+        opt(preceded(
+            char(':'),
+            cut(context(
+                "an arg type - atom, integer, float, date, enum(a|b) or ref(name/arity)",
+                parse_arg_type,
+            )),
+        )),
+        space0,
+        parse_description,
+    ))(i)
+    .map(|(leftover, (name, arg_type, _, desc))| {
+        let mut arg = NameDescription::new(name, &desc);
+        arg.arg_type = arg_type;
+        (leftover, arg)
+    })
+}
+
+// the rest of the line together with any continuation lines - lines are joined by "\n" and the
+// indentation past the continuation prefix is kept, so nested lists survive
+fn parse_description(i: &str) -> IResult<&str, String, VerboseError<&str>> {
+    pair(
+        terminated(not_line_ending, alt((line_ending, eof))),
+        many0(terminated(
+            alt((
+                preceded(tag(CONTINUATION), not_line_ending),
+                value("", pair(char('%'), space0)),
+            )),
+            alt((line_ending, eof)),
+        )),
     )(i)
-    .map(|(leftover, (name, desc))| {
-        (
-            leftover,
-            NameDescription {
-                name: name.to_owned(),
-                desc: desc.to_owned(),
-            },
-        )
+    .map(|(leftover, (first, rest))| {
+        let mut lines = vec![first.trim_end()];
+        lines.extend(rest.iter().map(|line| line.trim_end()));
+        (leftover, lines.join("\n").trim_end().to_string())
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_encode_arg_description() {
+        let input = "Age:integer the age in years";
+        let (leftover, arg) = parse_arg_description(input).unwrap();
+        assert_eq!(leftover, "");
+        assert_eq!(
+            arg,
+            NameDescription::new("Age", "the age in years").with_arg_type(ArgType::Integer)
+        );
+        assert_eq!(arg.encode(), input);
+
+        let (_, untyped) = parse_arg_description("Name the name").unwrap();
+        assert_eq!(untyped, NameDescription::new("Name", "the name"));
+
+        assert!(matches!(
+            parse_arg_description("Age:number the age"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_encode_multiline_description() {
+        let input = "parent someone's parent
+%   - by birth
+%     or adoption
+%
+%   see also ancestor/2
+% @see
+";
+        let (leftover, parsed) = parse_name_description(input).unwrap();
+        assert_eq!(leftover, "% @see\n");
+        assert_eq!(
+            parsed.desc,
+            "someone's parent\n- by birth\n  or adoption\n\nsee also ancestor/2"
+        );
+        assert_eq!(format!("{}\n{}", parsed.encode(), leftover), input);
+    }
+
+    #[test]
+    fn test_parse_encode_term_name() {
+        for name in ["parent", "lives in", "born, raised", "O'Brien", "Parent"] {
+            let term = NameDescription::new(name, "some description");
+            let encoded = term.encode_term();
+            assert_eq!(parse_name_description(&encoded), Ok(("", term)));
+        }
+        assert_eq!(
+            NameDescription::new("lives in", "where").encode_term(),
+            "'lives in' where"
+        );
+        // names written before they were quoted
+        let (_, legacy) = parse_name_description("lives_in where").unwrap();
+        assert_eq!(legacy.name, "lives_in");
+    }
+}
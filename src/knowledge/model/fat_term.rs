@@ -1,13 +1,14 @@
 use std::collections::HashSet;
 
-use nom::{error::VerboseError, IResult};
+use nom::{error::VerboseError, IResult, Offset};
 
 use super::{
     comment::{
         name_description::NameDescription,
         {parse_comment, Comment},
     },
-    term::{parse_term, Term},
+    parsing::{term_header_start, ParseError},
+    term::{parse_clause, parse_term, term_key, Term},
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -28,16 +29,21 @@ impl FatTerm {
         encoded
     }
 
-    pub fn add_referred_by(&mut self, term_name: &String) -> bool {
-        if !self.meta.referred_by.contains(term_name) {
-            self.meta.referred_by.push(term_name.to_owned());
+    // "name/arity" - the arity is the number of declared args
+    pub fn key(&self) -> String {
+        term_key(&self.meta.term.name, self.meta.args.len())
+    }
+
+    pub fn add_referred_by(&mut self, term_key: &String) -> bool {
+        if !self.meta.referred_by.contains(term_key) {
+            self.meta.referred_by.push(term_key.to_owned());
             return true;
         }
         false
     }
 
-    pub fn remove_referred_by(&mut self, term_name: &str) -> bool {
-        if let Some(idx) = self.meta.referred_by.iter().position(|x| x == term_name) {
+    pub fn remove_referred_by(&mut self, term_key: &str) -> bool {
+        if let Some(idx) = self.meta.referred_by.iter().position(|x| x == term_key) {
             self.meta.referred_by.remove(idx);
             return true;
         }
@@ -56,8 +62,8 @@ impl FatTerm {
         let mut mentioned_terms = HashSet::<String>::new();
 
         for rule in self.term.rules.iter() {
-            for body_term in &rule.body {
-                mentioned_terms.insert(body_term.name.clone());
+            for body_term in rule.body_terms() {
+                mentioned_terms.insert(body_term.key());
             }
         }
         mentioned_terms
@@ -73,7 +79,26 @@ impl Default for FatTerm {
     }
 }
 
-pub fn parse_fat_term(i: &str) -> IResult<&str, FatTerm, VerboseError<&str>> {
+// parses a single term - whatever follows its clauses has to be the start of the next term
+pub fn parse_fat_term(i: &str) -> Result<(&str, FatTerm), ParseError> {
+    let (leftover, fat_term) = fat_term_parser(i).map_err(|err| ParseError::from_nom(i, err))?;
+
+    if leftover.is_empty() || term_header_start(leftover).is_ok() {
+        return Ok((leftover, fat_term));
+    }
+    // the clauses are collected until the first one that doesn't parse, so a broken clause only
+    // shows up as leftover - parsing it on its own tells what's wrong with it
+    match parse_clause(leftover) {
+        Err(err) => Err(ParseError::from_nom(i, err)),
+        Ok(_) => {
+            let mut parse_error = ParseError::at(i, i.offset(leftover));
+            parse_error.expected = "the header of the next term".to_string();
+            Err(parse_error)
+        }
+    }
+}
+
+fn fat_term_parser(i: &str) -> IResult<&str, FatTerm, VerboseError<&str>> {
     let (leftover, meta) = parse_comment(i)?;
     let (leftover, term) = parse_term(leftover)?;
 
@@ -84,7 +109,7 @@ pub fn parse_fat_term(i: &str) -> IResult<&str, FatTerm, VerboseError<&str>> {
 mod test {
     use super::*;
     use crate::knowledge::model::term::{
-        args_binding::ArgsBinding, bound_term::BoundTerm, rule::Rule,
+        arg::Arg, args_binding::ArgsBinding, bound_term::BoundTerm, goal::Goal, rule::Rule,
     };
     #[test]
     fn test_parse_encode() {
@@ -110,29 +135,41 @@ father(Father,Child):-parent(Father,Child),male(Father).
             Term::new(
                 &[
                     ArgsBinding {
-                        binding: vec!["stefan".to_string(), "petko".to_string()],
+                        binding: vec![
+                            Arg::Atom("stefan".to_string()),
+                            Arg::Atom("petko".to_string()),
+                        ],
                     },
                     ArgsBinding {
-                        binding: vec!["hristo".to_string(), "stoichko".to_string()],
+                        binding: vec![
+                            Arg::Atom("hristo".to_string()),
+                            Arg::Atom("stoichko".to_string()),
+                        ],
                     },
                 ],
                 &[Rule {
                     head: ArgsBinding {
-                        binding: vec!["Father".to_string(), "Child".to_string()],
+                        binding: vec![
+                            Arg::Variable("Father".to_string()),
+                            Arg::Variable("Child".to_string()),
+                        ],
                     },
                     body: vec![
-                        BoundTerm {
+                        Goal::Term(BoundTerm {
                             name: "parent".to_string(),
                             arg_bindings: ArgsBinding {
-                                binding: vec!["Father".to_string(), "Child".to_string()],
+                                binding: vec![
+                                    Arg::Variable("Father".to_string()),
+                                    Arg::Variable("Child".to_string()),
+                                ],
                             },
-                        },
-                        BoundTerm {
+                        }),
+                        Goal::Term(BoundTerm {
                             name: "male".to_string(),
                             arg_bindings: ArgsBinding {
-                                binding: vec!["Father".to_string()],
+                                binding: vec![Arg::Variable("Father".to_string())],
                             },
-                        },
+                        }),
                     ],
                 }],
             ),
@@ -141,4 +178,95 @@ father(Father,Child):-parent(Father,Child),male(Father).
         assert_eq!(encoded, input);
         assert_eq!(parsed, Ok(("", expected)));
     }
+
+    #[test]
+    fn test_parse_encode_proposition() {
+        let input = r"% -raining whether it is raining right now
+% @see umbrella
+raining.
+";
+        let (leftover, parsed) = parse_fat_term(input).unwrap();
+
+        assert_eq!(leftover, "");
+        assert!(parsed.meta.args.is_empty());
+        assert_eq!(parsed.term.facts, vec![ArgsBinding { binding: vec![] }]);
+        assert_eq!(parsed.encode(), input);
+    }
+
+    #[test]
+    fn test_built_ins_are_not_mentioned_terms() {
+        let input = r"% -older whether the first person is older than the second
+% @arg First the first person
+% @arg Second the second person
+% @see senior
+older(X,Y):-age(X,A),age(Y,B),A>B.
+older(X,Y):-born(X,A),born(Y,B),\+ A>=B,X\==Y.
+";
+        let (leftover, parsed) = parse_fat_term(input).unwrap();
+
+        assert_eq!(leftover, "");
+        assert_eq!(
+            parsed.mentioned_terms(),
+            HashSet::from(["age/2".to_string(), "born/2".to_string()])
+        );
+        assert_eq!(parsed.encode(), input);
+    }
+
+    #[test]
+    fn test_parse_error_position() {
+        let input = r"% -father a father is a parent that's male
+% @arg FatherName the name of the father
+% @arg ChildName the name of the child
+% @see parent,male
+father(stefan,petko).
+father(Father,Child):-parent(Father,Child)male(Father).
+";
+        assert_eq!(
+            parse_fat_term(input),
+            Err(ParseError {
+                line: 6,
+                column: 43,
+                snippet: "male(Father).".to_string(),
+                expected: "',' or '.' after a goal".to_string(),
+            })
+        );
+
+        let missing_header = "father(stefan,petko).\n";
+        let parse_error = parse_fat_term(missing_header).unwrap_err();
+        assert_eq!((parse_error.line, parse_error.column), (1, 1));
+        assert_eq!(parse_error.expected, "'% -name description' header");
+    }
+
+    #[test]
+    fn test_parse_hand_edited_layout() {
+        let input = r"
+% -father  a father is a parent that's male
+% @arg FatherName the name of the father
+% @arg ChildName the name of the child
+%  @see parent, male
+
+% the first known ones
+father(stefan, petko).
+father(Father, Child) :-
+    parent(Father, Child), % has to be a parent
+    male(Father).
+
+father( hristo , stoichko ) .
+% -male the next term
+";
+        let (leftover, parsed) = parse_fat_term(input).unwrap();
+
+        assert_eq!(leftover, "% -male the next term\n");
+        assert_eq!(
+            parsed.encode(),
+            r"% -father a father is a parent that's male
+% @arg FatherName the name of the father
+% @arg ChildName the name of the child
+% @see parent,male
+father(stefan,petko).
+father(hristo,stoichko).
+father(Father,Child):-parent(Father,Child),male(Father).
+"
+        );
+    }
 }
@@ -1,4 +1,15 @@
 use std::io;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, multispace1, not_line_ending, space0},
+    combinator::{not, value},
+    error::{VerboseError, VerboseErrorKind},
+    multi::many0_count,
+    sequence::{delimited, preceded, tuple},
+    IResult, Offset,
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +17,160 @@ pub enum Error {
     #[error(transparent)]
     Io(#[from] io::Error),
 
-    #[error("parse failure: {}", verbose_err)]
-    Parse { verbose_err: String },
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+// Where and why parsing stopped - line and column are 1-based, the snippet is the rest of the
+// offending line
+#[derive(Error, Debug, PartialEq, Clone)]
+#[error("parse failure at line {line}, column {column}: expected {expected}, found \"{snippet}\"")]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub snippet: String,
+    pub expected: String,
+}
+
+const SNIPPET_MAX_CHARS: usize = 40;
+
+impl ParseError {
+    // `input` is what was handed to the parser - the failed position is located by the remaining
+    // input in the innermost error
+    pub fn new(input: &str, err: &VerboseError<&str>) -> Self {
+        let remaining = err.errors.first().map(|(r, _)| *r).unwrap_or(input);
+        let mut parse_error = Self::at(input, input.offset(remaining));
+        parse_error.expected = expected_hint(err);
+        parse_error
+    }
+
+    pub fn from_nom(input: &str, err: nom::Err<VerboseError<&str>>) -> Self {
+        match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => Self::new(input, &err),
+            nom::Err::Incomplete(_) => {
+                let mut parse_error = Self::at(input, input.len());
+                parse_error.expected = "more input".to_string();
+                parse_error
+            }
+        }
+    }
+
+    pub(crate) fn at(input: &str, offset: usize) -> Self {
+        let consumed = &input[..offset];
+        let line = consumed.matches('\n').count() + 1;
+        let line_start = consumed.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+        let column = consumed[line_start..].chars().count() + 1;
+
+        let rest = &input[offset..];
+        let rest_of_line = rest.split('\n').next().unwrap_or_default();
+        let snippet = rest_of_line.chars().take(SNIPPET_MAX_CHARS).collect();
+
+        Self {
+            line,
+            column,
+            snippet,
+            expected: String::new(),
+        }
+    }
+
+    // positions the error within a bigger text, when only a part of it (starting right after
+    // `preceding`) was parsed
+    pub fn offset_by(mut self, preceding: &str) -> Self {
+        if self.line == 1 {
+            let last_line_start = preceding.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+            self.column += preceding[last_line_start..].chars().count();
+        }
+        self.line += preceding.matches('\n').count();
+        self
+    }
+}
+
+// a context added right where parsing stopped is the most specific hint, then the innermost char
+// that was expected - nom's own error kinds are the fallback
+fn expected_hint(err: &VerboseError<&str>) -> String {
+    let stopped_at = err.errors.first().map(|(remaining, _)| remaining.len());
+    let context_at_stop = err.errors.iter().find_map(|(remaining, kind)| match kind {
+        VerboseErrorKind::Context(context) if Some(remaining.len()) == stopped_at => {
+            Some(context.to_string())
+        }
+        _ => None,
+    });
+    if let Some(context) = context_at_stop {
+        return context;
+    }
+    for (_, kind) in &err.errors {
+        match kind {
+            VerboseErrorKind::Context(context) => return context.to_string(),
+            VerboseErrorKind::Char(c) => return format!("{:?}", c),
+            VerboseErrorKind::Nom(_) => continue,
+        }
+    }
+    match err.errors.first() {
+        Some((_, VerboseErrorKind::Nom(kind))) => kind.description().to_lowercase(),
+        _ => "more input".to_string(),
+    }
+}
+
+// skips whitespace (newlines included) and "%" comments running to the end of the line
+pub(crate) fn layout(i: &str) -> IResult<&str, (), VerboseError<&str>> {
+    value((), many0_count(alt((multispace1, line_comment))))(i)
+}
+
+// same as layout, but stops at the "% -name description" header that starts the next term
+pub(crate) fn clause_layout(i: &str) -> IResult<&str, (), VerboseError<&str>> {
+    value(
+        (),
+        many0_count(alt((
+            multispace1,
+            preceded(not(term_header_start), line_comment),
+        ))),
+    )(i)
+}
+
+// "% -", tolerating spaces between the "%" and the "-" - three or more spaces are a continuation
+// of a description that happens to be a "- bullet"
+pub(crate) fn term_header_start(i: &str) -> IResult<&str, (), VerboseError<&str>> {
+    value((), tuple((not(tag("%   ")), char('%'), space0, char('-'))))(i)
+}
+
+// a token that may be surrounded by layout - "a , b" and "a,b" are the same thing
+pub(crate) fn padded<'a, O, F>(
+    inner: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O, VerboseError<&'a str>>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, O, VerboseError<&'a str>>,
+{
+    delimited(layout, inner, layout)
+}
+
+fn line_comment(i: &str) -> IResult<&str, &str, VerboseError<&str>> {
+    preceded(char('%'), not_line_ending)(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position() {
+        let input = "first line\nsecond line";
+        let parse_error = ParseError::at(input, input.find("line\n").unwrap());
+        assert_eq!((parse_error.line, parse_error.column), (1, 7));
+        assert_eq!(parse_error.snippet, "line");
+
+        let parse_error = ParseError::at(input, input.rfind("line").unwrap());
+        assert_eq!((parse_error.line, parse_error.column), (2, 8));
+
+        let parse_error = parse_error.offset_by("a\nb\n");
+        assert_eq!((parse_error.line, parse_error.column), (4, 8));
+    }
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(layout("  % a comment\n\n\tx"), Ok(("x", ())));
+        assert_eq!(
+            clause_layout("\n% note\n%  -next term"),
+            Ok(("%  -next term", ()))
+        );
+    }
 }
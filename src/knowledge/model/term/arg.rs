@@ -0,0 +1,429 @@
+use std::hash::{Hash, Hasher};
+
+use nom::{
+    branch::alt,
+    bytes::complete::{escaped_transform, is_not, tag, take_while},
+    character::complete::{char, digit1, one_of, satisfy},
+    combinator::{all_consuming, map, map_res, opt, recognize, value},
+    error::VerboseError,
+    multi::{fold_many0, separated_list1},
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    IResult,
+};
+
+use crate::knowledge::model::parsing::{layout, padded};
+
+use super::args_binding::{parse_args_binding, ArgsBinding};
+
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+pub enum Arg {
+    Atom(String),
+    Variable(String),
+    Integer(i64),
+    Float(Float),
+    String(String),
+    // the optional tail is the "T" in "[H|T]"
+    List(Vec<Arg>, Option<Box<Arg>>),
+    Compound(String, ArgsBinding),
+}
+
+// f64 is neither Eq nor Hash, but terms need to be both (they are used in DragAndDrop lists), so
+// floats are compared by their bit representation
+#[derive(Debug, Clone, Copy)]
+pub struct Float(pub f64);
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for Float {}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl Arg {
+    pub fn encode(&self) -> String {
+        match self {
+            Arg::Atom(name) => encode_atom(name),
+            Arg::Variable(name) => name.to_owned(),
+            Arg::Integer(i) => i.to_string(),
+            Arg::Float(f) => encode_float(f.0),
+            Arg::String(s) => {
+                let mut encoded = String::with_capacity(s.len() + 2);
+                encoded.push('"');
+                for c in s.chars() {
+                    match c {
+                        '"' => encoded.push_str("\\\""),
+                        '\\' => encoded.push_str("\\\\"),
+                        '\n' => encoded.push_str("\\n"),
+                        _ => encoded.push(c),
+                    }
+                }
+                encoded.push('"');
+                encoded
+            }
+            Arg::List(items, tail) => {
+                let mut encoded = String::from("[");
+                let items: Vec<String> = items.iter().map(|item| item.encode()).collect();
+                encoded.push_str(&items.join(","));
+                if let Some(tail) = tail {
+                    encoded.push('|');
+                    encoded.push_str(&tail.encode());
+                }
+                encoded.push(']');
+                encoded
+            }
+            Arg::Compound(name, args) => {
+                let mut encoded = encode_atom(name);
+                encoded.push('(');
+                encoded.push_str(&args.encode());
+                encoded.push(')');
+                encoded
+            }
+        }
+    }
+
+    // a value typed in for a fact - facts hold constants, so a capitalised word is the atom it
+    // reads as rather than a variable, nested ones included. "_" and the names starting with it
+    // are still variables - the value doesn't matter
+    pub fn constant(value: &str) -> Self {
+        Arg::from(value).into_constant()
+    }
+
+    fn into_constant(self) -> Self {
+        match self {
+            Arg::Variable(name) if !name.starts_with('_') => Arg::Atom(name),
+            Arg::List(items, tail) => Arg::List(
+                items.into_iter().map(Arg::into_constant).collect(),
+                tail.map(|tail| Box::new(tail.into_constant())),
+            ),
+            Arg::Compound(name, args) => Arg::Compound(
+                name,
+                ArgsBinding {
+                    binding: args.binding.into_iter().map(Arg::into_constant).collect(),
+                },
+            ),
+            arg => arg,
+        }
+    }
+
+    pub fn is_variable(&self) -> bool {
+        matches!(self, Arg::Variable(_))
+    }
+
+    // every occurrence of a variable, the ones nested in lists and compounds included
+    pub fn variables(&self) -> Vec<&str> {
+        match self {
+            Arg::Variable(name) => vec![name],
+            Arg::List(items, tail) => items
+                .iter()
+                .chain(tail.as_deref())
+                .flat_map(|arg| arg.variables())
+                .collect(),
+            Arg::Compound(_, args) => args.variables(),
+            _ => vec![],
+        }
+    }
+}
+
+// user-entered values that are not a valid argument on their own are taken as a plain atom
+impl From<&str> for Arg {
+    fn from(value: &str) -> Self {
+        match all_consuming(parse_arg)(value) {
+            Ok((_, arg)) => arg,
+            Err(_) => Arg::Atom(value.to_owned()),
+        }
+    }
+}
+
+// atoms that don't look like a plain "some_const" (spaces, capital first letter, punctuation) are
+// single-quoted, otherwise they'd be read as variables or as several tokens
+pub fn encode_atom(name: &str) -> String {
+    if is_plain_atom(name) {
+        return name.to_owned();
+    }
+    let mut encoded = String::with_capacity(name.len() + 2);
+    encoded.push('\'');
+    for c in name.chars() {
+        match c {
+            '\'' => encoded.push_str("''"),
+            '\\' => encoded.push_str("\\\\"),
+            '\n' => encoded.push_str("\\n"),
+            _ => encoded.push(c),
+        }
+    }
+    encoded.push('\'');
+    encoded
+}
+
+fn is_plain_atom(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.is_lowercase() && chars.all(is_name_char),
+        None => false,
+    }
+}
+
+// Rust's float formatting drops the ".0" for exponents ("1e20"), which Prolog can't read
+fn encode_float(f: f64) -> String {
+    let encoded = format!("{:?}", f);
+    if encoded.contains('.') || !encoded.contains('e') {
+        return encoded;
+    }
+    encoded.replacen('e', ".0e", 1)
+}
+
+// parses "some_const", "'Some const'", "SomeVar", "42", "4.2", "\"some text\"", "[a,B|T]" and "name(args)"
+pub fn parse_arg(i: &str) -> IResult<&str, Arg, VerboseError<&str>> {
+    alt((
+        parse_string,
+        parse_list,
+        parse_float,
+        parse_integer,
+        parse_variable,
+        parse_atom_or_compound,
+    ))(i)
+}
+
+fn parse_string(i: &str) -> IResult<&str, Arg, VerboseError<&str>> {
+    map(
+        delimited(
+            char('"'),
+            map(
+                opt(escaped_transform(
+                    is_not("\\\""),
+                    '\\',
+                    alt((
+                        value("\\", tag("\\")),
+                        value("\"", tag("\"")),
+                        value("\n", tag("n")),
+                    )),
+                )),
+                Option::unwrap_or_default,
+            ),
+            char('"'),
+        ),
+        Arg::String,
+    )(i)
+}
+
+fn parse_list(i: &str) -> IResult<&str, Arg, VerboseError<&str>> {
+    alt((
+        value(
+            Arg::List(vec![], None),
+            tuple((char('['), layout, char(']'))),
+        ),
+        map(
+            delimited(
+                terminated(char('['), layout),
+                pair(
+                    separated_list1(padded(char(',')), parse_arg),
+                    opt(preceded(padded(char('|')), parse_arg)),
+                ),
+                preceded(layout, char(']')),
+            ),
+            |(items, tail)| Arg::List(items, tail.map(Box::new)),
+        ),
+    ))(i)
+}
+
+fn parse_float(i: &str) -> IResult<&str, Arg, VerboseError<&str>> {
+    map_res(
+        recognize(tuple((
+            opt(char('-')),
+            digit1,
+            char('.'),
+            digit1,
+            opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+        ))),
+        |f: &str| f.parse::<f64>().map(|f| Arg::Float(Float(f))),
+    )(i)
+}
+
+fn parse_integer(i: &str) -> IResult<&str, Arg, VerboseError<&str>> {
+    map_res(recognize(pair(opt(char('-')), digit1)), |d: &str| {
+        d.parse::<i64>().map(Arg::Integer)
+    })(i)
+}
+
+fn parse_variable(i: &str) -> IResult<&str, Arg, VerboseError<&str>> {
+    map(
+        recognize(pair(
+            satisfy(|c| c.is_uppercase() || c == '_'),
+            take_while(is_name_char),
+        )),
+        |name: &str| Arg::Variable(name.to_owned()),
+    )(i)
+}
+
+fn parse_atom_or_compound(i: &str) -> IResult<&str, Arg, VerboseError<&str>> {
+    map(
+        pair(
+            parse_atom_name,
+            opt(delimited(
+                terminated(char('('), layout),
+                parse_args_binding,
+                preceded(layout, char(')')),
+            )),
+        ),
+        |(name, args)| match args {
+            Some(args) => Arg::Compound(name, args),
+            None => Arg::Atom(name),
+        },
+    )(i)
+}
+
+// parses "some_name" and "'Some name'" - the quotes are not part of the returned name
+pub(crate) fn parse_atom_name(i: &str) -> IResult<&str, String, VerboseError<&str>> {
+    alt((
+        map(
+            recognize(pair(
+                satisfy(|c| c.is_lowercase()),
+                take_while(is_name_char),
+            )),
+            str::to_owned,
+        ),
+        parse_quoted_atom,
+    ))(i)
+}
+
+pub(crate) fn parse_quoted_atom(i: &str) -> IResult<&str, String, VerboseError<&str>> {
+    delimited(
+        char('\''),
+        fold_many0(
+            alt((
+                is_not("'\\"),
+                value("'", tag("''")),
+                value("'", tag("\\'")),
+                value("\\", tag("\\\\")),
+                value("\n", tag("\\n")),
+            )),
+            String::new,
+            |mut name, chunk| {
+                name.push_str(chunk);
+                name
+            },
+        ),
+        char('\''),
+    )(i)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_encode_args() {
+        let cases = [
+            ("bob", Arg::Atom("bob".to_string())),
+            ("Bob", Arg::Variable("Bob".to_string())),
+            ("_", Arg::Variable("_".to_string())),
+            ("42", Arg::Integer(42)),
+            ("-7", Arg::Integer(-7)),
+            ("4.5", Arg::Float(Float(4.5))),
+            (
+                r#""quoted, \"text\"""#,
+                Arg::String("quoted, \"text\"".to_string()),
+            ),
+            ("[]", Arg::List(vec![], None)),
+            (
+                "[a,B|T]",
+                Arg::List(
+                    vec![Arg::Atom("a".to_string()), Arg::Variable("B".to_string())],
+                    Some(Box::new(Arg::Variable("T".to_string()))),
+                ),
+            ),
+            (
+                "car(red,2010)",
+                Arg::Compound(
+                    "car".to_string(),
+                    ArgsBinding::new(&[Arg::Atom("red".to_string()), Arg::Integer(2010)]),
+                ),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse_arg(input), Ok(("", expected.clone())));
+            assert_eq!(expected.encode(), input);
+        }
+    }
+
+    #[test]
+    fn test_encode_float_with_exponent() {
+        assert_eq!(Arg::Float(Float(1e20)).encode(), "1.0e20");
+        assert_eq!(Arg::from("1.0e20"), Arg::Float(Float(1e20)));
+    }
+
+    #[test]
+    fn test_from_user_input() {
+        assert_eq!(Arg::from("age(X)"), parse_arg("age(X)").unwrap().1);
+        assert_eq!(Arg::from("a b"), Arg::Atom("a b".to_string()));
+    }
+
+    #[test]
+    fn test_constant() {
+        let cases = [
+            ("Bob", Arg::Atom("Bob".to_string())),
+            ("'New York'", Arg::Atom("New York".to_string())),
+            ("_", Arg::Variable("_".to_string())),
+            ("_Ignored", Arg::Variable("_Ignored".to_string())),
+            ("42", Arg::Integer(42)),
+            (
+                "home(Bob)",
+                Arg::Compound(
+                    "home".to_string(),
+                    ArgsBinding::new(&[Arg::Atom("Bob".to_string())]),
+                ),
+            ),
+        ];
+        for (input, expected) in cases {
+            let constant = Arg::constant(input);
+            assert_eq!(constant, expected);
+            // what the fact placeholder shows for editing reads back the same
+            assert_eq!(Arg::constant(&constant.encode()), expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_encode_quoted_atoms() {
+        let cases = [
+            ("'New York'", Arg::Atom("New York".to_string())),
+            ("'O''Brien'", Arg::Atom("O'Brien".to_string())),
+            ("'a,b(c)'", Arg::Atom("a,b(c)".to_string())),
+            (r"'back\\slash'", Arg::Atom(r"back\slash".to_string())),
+            ("''", Arg::Atom("".to_string())),
+            (
+                "'Home'('New York')",
+                Arg::Compound(
+                    "Home".to_string(),
+                    ArgsBinding::new(&[Arg::Atom("New York".to_string())]),
+                ),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse_arg(input), Ok(("", expected.clone())));
+            assert_eq!(expected.encode(), input);
+        }
+        // redundant quotes are dropped
+        assert_eq!(Arg::from("'bob'").encode(), "bob");
+        assert_eq!(Arg::from(r"'O\'Brien'"), Arg::Atom("O'Brien".to_string()));
+    }
+
+    #[test]
+    fn test_user_input_round_trips() {
+        for input in ["New York", "O'Brien", "a, b", "x(y"] {
+            let encoded = Arg::from(input).encode();
+            assert_eq!(parse_arg(&encoded), Ok(("", Arg::Atom(input.to_string()))));
+        }
+    }
+}
@@ -1,37 +1,33 @@
-use nom::{
-    bytes::complete::{tag, take_till1},
-    error::VerboseError,
-    multi::separated_list1,
-    IResult,
-};
+use nom::{character::complete::char, error::VerboseError, multi::separated_list1, IResult};
+
+use super::arg::{parse_arg, Arg};
+use crate::knowledge::model::parsing::padded;
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub struct ArgsBinding {
-    pub binding: Vec<String>,
+    pub binding: Vec<Arg>,
 }
 
 impl ArgsBinding {
-    pub fn new(binding: &[String]) -> Self {
+    pub fn new(binding: &[Arg]) -> Self {
         Self {
             binding: binding.to_vec(),
         }
     }
     pub fn encode(&self) -> String {
-        self.binding.join(",")
+        let encoded: Vec<String> = self.binding.iter().map(|arg| arg.encode()).collect();
+        encoded.join(",")
     }
-}
 
-pub fn parse_args_binding(i: &str) -> IResult<&str, ArgsBinding, VerboseError<&str>> {
-    separated_list1(tag(","), take_till1(is_end_of_args))(i).map(|(leftover, args)| {
-        (
-            leftover,
-            ArgsBinding {
-                binding: args.into_iter().map(|x| x.to_string()).collect(),
-            },
-        )
-    })
+    pub fn variables(&self) -> Vec<&str> {
+        self.binding
+            .iter()
+            .flat_map(|arg| arg.variables())
+            .collect()
+    }
 }
 
-fn is_end_of_args(c: char) -> bool {
-    c == ',' || c == ')'
+pub fn parse_args_binding(i: &str) -> IResult<&str, ArgsBinding, VerboseError<&str>> {
+    separated_list1(padded(char(',')), parse_arg)(i)
+        .map(|(leftover, args)| (leftover, ArgsBinding { binding: args }))
 }
@@ -1,11 +1,18 @@
 use nom::{
-    bytes::complete::take_until,
+    character::complete::char,
+    combinator::opt,
     error::VerboseError,
-    sequence::{delimited, tuple},
+    sequence::{delimited, preceded, terminated, tuple},
     IResult,
 };
 
-use super::args_binding::{parse_args_binding, ArgsBinding};
+use crate::knowledge::model::parsing::layout;
+
+use super::{
+    arg::{encode_atom, parse_atom_name},
+    args_binding::{parse_args_binding, ArgsBinding},
+    term_key,
+};
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub struct BoundTerm {
@@ -22,32 +29,74 @@ impl BoundTerm {
     }
     pub fn encode(&self) -> String {
         let mut encoded = String::new();
-        encoded.push_str(&self.name);
-        encoded.push('(');
-        encoded.push_str(&self.arg_bindings.encode());
-        encoded.push(')');
+        encoded.push_str(&encode_atom(&self.name));
+        // zero-arity terms are plain atoms - "raining" rather than "raining()"
+        if !self.arg_bindings.binding.is_empty() {
+            encoded.push('(');
+            encoded.push_str(&self.arg_bindings.encode());
+            encoded.push(')');
+        }
         encoded
     }
+
+    // the key of the term being called
+    pub fn key(&self) -> String {
+        term_key(&self.name, self.arg_bindings.binding.len())
+    }
 }
 
-// parses "some_term_name(some_const,SomeVar,_)"
+// parses "some_term_name(some_const,SomeVar,_)", "'Some term'(X)" and "some_term_name"
 pub fn parse_bound_term(i: &str) -> IResult<&str, BoundTerm, VerboseError<&str>> {
     let name_and_args = tuple((
-        take_until("("),
-        delimited(
-            nom::character::complete::char('('),
+        parse_atom_name,
+        // no layout before the "(" - "name (args)" is not a call in Prolog
+        opt(delimited(
+            terminated(char('('), layout),
             parse_args_binding,
-            nom::character::complete::char(')'),
-        ),
+            preceded(layout, char(')')),
+        )),
     ))(i);
 
     name_and_args.map(|(leftover, (name, args))| {
         (
             leftover,
             BoundTerm {
-                name: name.to_string(),
-                arg_bindings: args,
+                name,
+                arg_bindings: args.unwrap_or(ArgsBinding { binding: vec![] }),
             },
         )
     })
 }
+
+#[test]
+fn test_parse_encode_zero_arity() {
+    let parsed = parse_bound_term("debug_mode,");
+    assert_eq!(
+        parsed,
+        Ok((
+            ",",
+            BoundTerm::new("debug_mode", ArgsBinding { binding: vec![] })
+        ))
+    );
+    assert_eq!(parsed.unwrap().1.encode(), "debug_mode");
+}
+
+#[test]
+fn test_parse_encode_quoted_name() {
+    let input = r"'lives in'('O''Brien','New York')";
+    let parsed = parse_bound_term(input);
+    assert_eq!(
+        parsed,
+        Ok((
+            "",
+            BoundTerm::new(
+                "lives in",
+                ArgsBinding::new(&[
+                    super::arg::Arg::Atom("O'Brien".to_string()),
+                    super::arg::Arg::Atom("New York".to_string())
+                ])
+            )
+        ))
+    );
+    assert_eq!(parsed.unwrap().1.encode(), input);
+}
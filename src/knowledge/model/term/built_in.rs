@@ -0,0 +1,364 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, satisfy},
+    combinator::{map, not, value},
+    error::VerboseError,
+    multi::many0,
+    sequence::{delimited, pair, terminated, tuple},
+    IResult,
+};
+
+use super::arg::{parse_arg, Arg};
+use crate::knowledge::model::parsing::padded;
+
+// A call to one of Prolog's infix built-ins - "A>B", "X is A+1", "X\=Y". These are never stored
+// as terms, so they don't count towards the terms mentioned by a rule
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+pub struct BuiltIn {
+    pub left: Expression,
+    pub operator: Operator,
+    pub right: Expression,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Eq, Hash)]
+pub enum Operator {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    ArithmeticEqual,
+    ArithmeticNotEqual,
+    Is,
+    Unify,
+    NotUnify,
+    Identical,
+    NotIdentical,
+}
+
+// Operands of the built-ins - a plain argument or arithmetic over arguments
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+pub enum Expression {
+    Arg(Arg),
+    Operation(Box<Expression>, ArithmeticOperator, Box<Expression>),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Eq, Hash)]
+pub enum ArithmeticOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl BuiltIn {
+    pub fn new(left: Expression, operator: Operator, right: Expression) -> Self {
+        Self {
+            left,
+            operator,
+            right,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut encoded = self.left.encode();
+        push_separated(&mut encoded, self.operator.encode());
+        push_separated(&mut encoded, &self.right.encode());
+        encoded
+    }
+
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables = self.left.variables();
+        variables.extend(self.right.variables());
+        variables
+    }
+}
+
+impl Operator {
+    pub fn encode(&self) -> &'static str {
+        match self {
+            Operator::Less => "<",
+            Operator::LessOrEqual => "=<",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+            Operator::ArithmeticEqual => "=:=",
+            Operator::ArithmeticNotEqual => r"=\=",
+            // "XisY" would be read as a single atom
+            Operator::Is => " is ",
+            Operator::Unify => "=",
+            Operator::NotUnify => r"\=",
+            Operator::Identical => "==",
+            Operator::NotIdentical => r"\==",
+        }
+    }
+}
+
+impl Expression {
+    pub fn variables(&self) -> Vec<&str> {
+        match self {
+            Expression::Arg(arg) => arg.variables(),
+            Expression::Operation(left, _, right) => {
+                let mut variables = left.variables();
+                variables.extend(right.variables());
+                variables
+            }
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        match self {
+            Expression::Arg(arg) => arg.encode(),
+            Expression::Operation(left, operator, right) => {
+                let mut encoded = left.encode_operand(operator.precedence(), false);
+                push_separated(&mut encoded, operator.encode());
+                push_separated(
+                    &mut encoded,
+                    &right.encode_operand(operator.precedence(), true),
+                );
+                encoded
+            }
+        }
+    }
+
+    // the arithmetic operators are left-associative, so an operation on the right of an operator
+    // with the same precedence needs parentheses - "A-(B-C)"
+    fn encode_operand(&self, parent_precedence: u8, is_right: bool) -> String {
+        match self {
+            Expression::Operation(_, operator, _)
+                if operator.precedence() < parent_precedence
+                    || (is_right && operator.precedence() == parent_precedence) =>
+            {
+                format!("({})", self.encode())
+            }
+            _ => self.encode(),
+        }
+    }
+}
+
+impl ArithmeticOperator {
+    pub fn encode(&self) -> &'static str {
+        match self {
+            ArithmeticOperator::Add => "+",
+            ArithmeticOperator::Subtract => "-",
+            ArithmeticOperator::Multiply => "*",
+            ArithmeticOperator::Divide => "/",
+        }
+    }
+
+    // higher binds tighter
+    fn precedence(&self) -> u8 {
+        match self {
+            ArithmeticOperator::Add | ArithmeticOperator::Subtract => 1,
+            ArithmeticOperator::Multiply | ArithmeticOperator::Divide => 2,
+        }
+    }
+}
+
+// Prolog reads a run of symbol chars as a single atom - "X=-1" would be X followed by "=-", so a
+// negative number after an operator gets a space before it: "X= -1", "A- -1"
+fn push_separated(encoded: &mut String, token: &str) {
+    let is_symbol_char = |c: char| r"+-*/\^<>=~:.?@#&$".contains(c);
+    if encoded.chars().last().is_some_and(is_symbol_char)
+        && token.chars().next().is_some_and(is_symbol_char)
+    {
+        encoded.push(' ');
+    }
+    encoded.push_str(token);
+}
+
+// parses "A>B", "X is (A+B)*2", "X\==Y"
+pub fn parse_built_in(i: &str) -> IResult<&str, BuiltIn, VerboseError<&str>> {
+    map(
+        tuple((parse_expression, parse_operator, parse_expression)),
+        |(left, operator, right)| BuiltIn::new(left, operator, right),
+    )(i)
+}
+
+fn parse_operator(i: &str) -> IResult<&str, Operator, VerboseError<&str>> {
+    // longer operators first so that "=<" is not read as "=" followed by garbage
+    padded(alt((
+        value(Operator::ArithmeticEqual, tag("=:=")),
+        value(Operator::ArithmeticNotEqual, tag(r"=\=")),
+        value(Operator::NotIdentical, tag(r"\==")),
+        value(Operator::Identical, tag("==")),
+        value(Operator::LessOrEqual, tag("=<")),
+        value(Operator::GreaterOrEqual, tag(">=")),
+        value(Operator::NotUnify, tag(r"\=")),
+        value(Operator::Unify, tag("=")),
+        value(Operator::Less, tag("<")),
+        value(Operator::Greater, tag(">")),
+        // "is" must stand on its own - "X is_a Y" is not a call to is/2
+        value(
+            Operator::Is,
+            terminated(tag("is"), not(satisfy(|c| c.is_alphanumeric() || c == '_'))),
+        ),
+    )))(i)
+}
+
+fn parse_expression(i: &str) -> IResult<&str, Expression, VerboseError<&str>> {
+    parse_left_associative(
+        i,
+        parse_product,
+        padded(alt((
+            value(ArithmeticOperator::Add, char('+')),
+            value(ArithmeticOperator::Subtract, char('-')),
+        ))),
+    )
+}
+
+fn parse_product(i: &str) -> IResult<&str, Expression, VerboseError<&str>> {
+    parse_left_associative(
+        i,
+        parse_operand,
+        padded(alt((
+            value(ArithmeticOperator::Multiply, char('*')),
+            value(ArithmeticOperator::Divide, char('/')),
+        ))),
+    )
+}
+
+fn parse_operand(i: &str) -> IResult<&str, Expression, VerboseError<&str>> {
+    alt((
+        delimited(padded(char('(')), parse_expression, padded(char(')'))),
+        map(parse_arg, Expression::Arg),
+    ))(i)
+}
+
+fn parse_left_associative<'a>(
+    i: &'a str,
+    operand: fn(&'a str) -> IResult<&'a str, Expression, VerboseError<&'a str>>,
+    operator: impl FnMut(&'a str) -> IResult<&'a str, ArithmeticOperator, VerboseError<&'a str>>,
+) -> IResult<&'a str, Expression, VerboseError<&'a str>> {
+    let (leftover, first) = operand(i)?;
+    let (leftover, rest) = many0(pair(operator, operand))(leftover)?;
+
+    let expression = rest.into_iter().fold(first, |left, (operator, right)| {
+        Expression::Operation(Box::new(left), operator, Box::new(right))
+    });
+    Ok((leftover, expression))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge::model::term::arg::Float;
+
+    fn var(name: &str) -> Expression {
+        Expression::Arg(Arg::Variable(name.to_string()))
+    }
+
+    #[test]
+    fn test_parse_encode_built_ins() {
+        let cases = [
+            ("A>B", BuiltIn::new(var("A"), Operator::Greater, var("B"))),
+            (
+                "A=<B",
+                BuiltIn::new(var("A"), Operator::LessOrEqual, var("B")),
+            ),
+            (
+                r"X\==Y",
+                BuiltIn::new(var("X"), Operator::NotIdentical, var("Y")),
+            ),
+            (
+                "X=bob",
+                BuiltIn::new(
+                    var("X"),
+                    Operator::Unify,
+                    Expression::Arg(Arg::Atom("bob".to_string())),
+                ),
+            ),
+            (
+                "X is A+B*2",
+                BuiltIn::new(
+                    var("X"),
+                    Operator::Is,
+                    Expression::Operation(
+                        Box::new(var("A")),
+                        ArithmeticOperator::Add,
+                        Box::new(Expression::Operation(
+                            Box::new(var("B")),
+                            ArithmeticOperator::Multiply,
+                            Box::new(Expression::Arg(Arg::Integer(2))),
+                        )),
+                    ),
+                ),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse_built_in(input), Ok(("", expected.clone())));
+            assert_eq!(expected.encode(), input);
+        }
+    }
+
+    #[test]
+    fn test_parse_encode_parenthesized_arithmetic() {
+        for input in ["X is (A+B)*2", "X is A-(B-C)", "X is A-B-C", "X=:=A/(B*C)"] {
+            let (leftover, built_in) = parse_built_in(input).unwrap();
+            assert_eq!(leftover, "");
+            assert_eq!(built_in.encode(), input);
+        }
+    }
+
+    #[test]
+    fn test_encode_parse_negative_numbers() {
+        let int = |i| Expression::Arg(Arg::Integer(i));
+        let cases = [
+            (BuiltIn::new(var("X"), Operator::Unify, int(-1)), "X= -1"),
+            (BuiltIn::new(var("A"), Operator::Greater, int(-1)), "A> -1"),
+            (
+                BuiltIn::new(
+                    var("X"),
+                    Operator::ArithmeticNotEqual,
+                    Expression::Arg(Arg::Float(Float(-1.5))),
+                ),
+                r"X=\= -1.5",
+            ),
+            (
+                BuiltIn::new(
+                    var("X"),
+                    Operator::Is,
+                    Expression::Operation(
+                        Box::new(var("A")),
+                        ArithmeticOperator::Subtract,
+                        Box::new(int(-1)),
+                    ),
+                ),
+                "X is A- -1",
+            ),
+            (
+                BuiltIn::new(
+                    var("X"),
+                    Operator::Is,
+                    Expression::Operation(
+                        Box::new(int(-2)),
+                        ArithmeticOperator::Multiply,
+                        Box::new(var("B")),
+                    ),
+                ),
+                "X is -2*B",
+            ),
+        ];
+
+        for (built_in, expected) in cases {
+            assert_eq!(built_in.encode(), expected);
+            assert_eq!(parse_built_in(expected), Ok(("", built_in)));
+        }
+    }
+
+    #[test]
+    fn test_parse_with_layout() {
+        let (leftover, built_in) = parse_built_in("X is ( A + B ) * 2").unwrap();
+        assert_eq!(leftover, "");
+        assert_eq!(built_in.encode(), "X is (A+B)*2");
+
+        let (leftover, built_in) = parse_built_in("A >= B").unwrap();
+        assert_eq!(leftover, "");
+        assert_eq!(built_in.encode(), "A>=B");
+    }
+
+    #[test]
+    fn test_if_then_is_not_a_built_in() {
+        assert!(parse_built_in("a->b").is_err());
+    }
+}
@@ -0,0 +1,393 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{map, opt},
+    error::VerboseError,
+    multi::separated_list1,
+    sequence::{delimited, preceded, tuple},
+    IResult,
+};
+
+use crate::knowledge::model::parsing::padded;
+
+use super::{
+    bound_term::{parse_bound_term, BoundTerm},
+    built_in::{parse_built_in, BuiltIn},
+};
+
+// A single goal in a rule body. The top level of a body is a conjunction, so it is kept as a
+// Vec<Goal> in the Rule itself - nested conjunctions only show up as branches of the other
+// connectives
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+pub enum Goal {
+    Term(BoundTerm),
+    // A>B, X is A+1, ...
+    BuiltIn(BuiltIn),
+    // \+ goal
+    Not(Box<Goal>),
+    // goal, goal
+    Conjunction(Vec<Goal>),
+    // ( goal ; goal )
+    Disjunction(Vec<Goal>),
+    // ( condition -> then ; otherwise )
+    IfThenElse {
+        condition: Box<Goal>,
+        then: Box<Goal>,
+        otherwise: Option<Box<Goal>>,
+    },
+}
+
+const NOT: &str = r"\+";
+const OR: &str = r";";
+const AND: &str = r",";
+const IF_THEN: &str = r"->";
+
+impl Goal {
+    pub fn encode(&self) -> String {
+        match self {
+            Goal::Term(bound_term) => bound_term.encode(),
+            Goal::BuiltIn(built_in) => built_in.encode(),
+            // always followed by a space - "\+\+a" or "\+-1<X" would be read as a single atom
+            Goal::Not(goal) => match goal.as_ref() {
+                // "\+(a,b)" would be read as a call to \+/2
+                Goal::Conjunction(_) => format!("{} ({})", NOT, goal.encode()),
+                // disjunctions and if-then-elses bring their own parentheses
+                _ => format!("{} {}", NOT, goal.encode()),
+            },
+            Goal::Conjunction(goals) => encode_joined(goals, AND),
+            Goal::Disjunction(goals) => format!("({})", encode_joined(goals, OR)),
+            Goal::IfThenElse {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let mut encoded = format!("({}{}{}", condition.encode(), IF_THEN, then.encode());
+                if let Some(otherwise) = otherwise {
+                    encoded.push_str(OR);
+                    encoded.push_str(&otherwise.encode());
+                }
+                encoded.push(')');
+                encoded
+            }
+        }
+    }
+
+    pub fn bound_terms(&self) -> Vec<&BoundTerm> {
+        match self {
+            Goal::Term(bound_term) => vec![bound_term],
+            Goal::BuiltIn(_) => vec![],
+            Goal::Not(goal) => goal.bound_terms(),
+            Goal::Conjunction(goals) | Goal::Disjunction(goals) => {
+                goals.iter().flat_map(|g| g.bound_terms()).collect()
+            }
+            Goal::IfThenElse {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let mut bound_terms = condition.bound_terms();
+                bound_terms.extend(then.bound_terms());
+                if let Some(otherwise) = otherwise {
+                    bound_terms.extend(otherwise.bound_terms());
+                }
+                bound_terms
+            }
+        }
+    }
+
+    // every occurrence of a variable in the goal, in the order they are written
+    pub fn variables(&self) -> Vec<&str> {
+        match self {
+            Goal::Term(bound_term) => bound_term.arg_bindings.variables(),
+            Goal::BuiltIn(built_in) => built_in.variables(),
+            Goal::Not(goal) => goal.variables(),
+            Goal::Conjunction(goals) | Goal::Disjunction(goals) => {
+                goals.iter().flat_map(|g| g.variables()).collect()
+            }
+            Goal::IfThenElse {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let mut variables = condition.variables();
+                variables.extend(then.variables());
+                if let Some(otherwise) = otherwise {
+                    variables.extend(otherwise.variables());
+                }
+                variables
+            }
+        }
+    }
+
+    pub fn bound_terms_mut(&mut self) -> Vec<&mut BoundTerm> {
+        match self {
+            Goal::Term(bound_term) => vec![bound_term],
+            Goal::BuiltIn(_) => vec![],
+            Goal::Not(goal) => goal.bound_terms_mut(),
+            Goal::Conjunction(goals) | Goal::Disjunction(goals) => {
+                goals.iter_mut().flat_map(|g| g.bound_terms_mut()).collect()
+            }
+            Goal::IfThenElse {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let mut bound_terms = condition.bound_terms_mut();
+                bound_terms.extend(then.bound_terms_mut());
+                if let Some(otherwise) = otherwise {
+                    bound_terms.extend(otherwise.bound_terms_mut());
+                }
+                bound_terms
+            }
+        }
+    }
+
+    // Drops every call to the term with `term_key` from the goal. Connectives that are left without meaning
+    // are dropped as a whole - e.g. an if-then-else whose condition was removed
+    pub fn without_term(self, term_key: &str) -> Option<Goal> {
+        match self {
+            Goal::Term(bound_term) => {
+                if bound_term.key() == term_key {
+                    None
+                } else {
+                    Some(Goal::Term(bound_term))
+                }
+            }
+            Goal::BuiltIn(built_in) => Some(Goal::BuiltIn(built_in)),
+            Goal::Not(goal) => goal
+                .without_term(term_key)
+                .map(|goal| Goal::Not(Box::new(goal))),
+            Goal::Conjunction(goals) => {
+                let mut goals = without_term_in_all(goals, term_key);
+                match goals.len() {
+                    0 => None,
+                    1 => goals.pop(),
+                    _ => Some(Goal::Conjunction(goals)),
+                }
+            }
+            Goal::Disjunction(goals) => {
+                let mut goals = without_term_in_all(goals, term_key);
+                match goals.len() {
+                    0 => None,
+                    1 => goals.pop(),
+                    _ => Some(Goal::Disjunction(goals)),
+                }
+            }
+            Goal::IfThenElse {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = condition.without_term(term_key)?;
+                let then = then.without_term(term_key)?;
+                Some(Goal::IfThenElse {
+                    condition: Box::new(condition),
+                    then: Box::new(then),
+                    otherwise: otherwise
+                        .and_then(|otherwise| otherwise.without_term(term_key))
+                        .map(Box::new),
+                })
+            }
+        }
+    }
+}
+
+fn encode_joined(goals: &[Goal], separator: &str) -> String {
+    let encoded: Vec<String> = goals.iter().map(|g| g.encode()).collect();
+    encoded.join(separator)
+}
+
+fn without_term_in_all(goals: Vec<Goal>, term_key: &str) -> Vec<Goal> {
+    goals
+        .into_iter()
+        .filter_map(|g| g.without_term(term_key))
+        .collect()
+}
+
+// parses the body of a rule "a(X),\+b(X),(c(X);d(X)),(e->f;g)" - the top-level conjunction is
+// returned as separate goals
+pub fn parse_body(i: &str) -> IResult<&str, Vec<Goal>, VerboseError<&str>> {
+    map(parse_disjunction, |goal| match goal {
+        Goal::Conjunction(goals) => goals,
+        goal => vec![goal],
+    })(i)
+}
+
+// precedence (loosest to tightest) follows the standard Prolog operators: ";", "->", ",", "\+"
+fn parse_disjunction(i: &str) -> IResult<&str, Goal, VerboseError<&str>> {
+    map(
+        tuple((
+            parse_if_then,
+            opt(preceded(padded(tag(OR)), parse_disjunction)),
+        )),
+        |(left, right)| match (left, right) {
+            (left, None) => left,
+            // "c -> t ; e" is an if-then-else rather than a disjunction
+            (
+                Goal::IfThenElse {
+                    condition,
+                    then,
+                    otherwise: None,
+                },
+                Some(otherwise),
+            ) => Goal::IfThenElse {
+                condition,
+                then,
+                otherwise: Some(Box::new(otherwise)),
+            },
+            (left, Some(right)) => {
+                let mut alternatives = vec![];
+                for alternative in [left, right] {
+                    match alternative {
+                        Goal::Disjunction(nested) => alternatives.extend(nested),
+                        alternative => alternatives.push(alternative),
+                    }
+                }
+                Goal::Disjunction(alternatives)
+            }
+        },
+    )(i)
+}
+
+fn parse_if_then(i: &str) -> IResult<&str, Goal, VerboseError<&str>> {
+    map(
+        tuple((
+            parse_conjunction,
+            opt(preceded(padded(tag(IF_THEN)), parse_if_then)),
+        )),
+        |(condition, then)| match then {
+            Some(then) => Goal::IfThenElse {
+                condition: Box::new(condition),
+                then: Box::new(then),
+                otherwise: None,
+            },
+            None => condition,
+        },
+    )(i)
+}
+
+fn parse_conjunction(i: &str) -> IResult<&str, Goal, VerboseError<&str>> {
+    map(separated_list1(padded(tag(AND)), parse_primary), |goals| {
+        let mut flattened = vec![];
+        for goal in goals {
+            match goal {
+                Goal::Conjunction(nested) => flattened.extend(nested),
+                goal => flattened.push(goal),
+            }
+        }
+        if flattened.len() == 1 {
+            flattened.remove(0)
+        } else {
+            Goal::Conjunction(flattened)
+        }
+    })(i)
+}
+
+fn parse_primary(i: &str) -> IResult<&str, Goal, VerboseError<&str>> {
+    alt((
+        map(preceded(padded(tag(NOT)), parse_primary), |goal| {
+            Goal::Not(Box::new(goal))
+        }),
+        delimited(padded(char('(')), parse_disjunction, padded(char(')'))),
+        // before the bound term as "a=b" starts with what looks like the term "a"
+        map(parse_built_in, Goal::BuiltIn),
+        map(parse_bound_term, Goal::Term),
+    ))(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge::model::term::{arg::Arg, args_binding::ArgsBinding};
+
+    fn term(name: &str) -> Goal {
+        Goal::Term(BoundTerm::new(
+            name,
+            ArgsBinding::new(&[Arg::Variable("X".to_string())]),
+        ))
+    }
+
+    #[test]
+    fn test_parse_encode_goals() {
+        let input = r"a(X),\+ b(X),(c(X);d(X)),(e(X)->f(X);g(X)),\+ (h(X),i(X))";
+        let expected = vec![
+            term("a"),
+            Goal::Not(Box::new(term("b"))),
+            Goal::Disjunction(vec![term("c"), term("d")]),
+            Goal::IfThenElse {
+                condition: Box::new(term("e")),
+                then: Box::new(term("f")),
+                otherwise: Some(Box::new(term("g"))),
+            },
+            Goal::Not(Box::new(Goal::Conjunction(vec![term("h"), term("i")]))),
+        ];
+
+        assert_eq!(parse_body(input), Ok(("", expected.clone())));
+        assert_eq!(encode_joined(&expected, AND), input);
+    }
+
+    #[test]
+    fn test_encode_parse_nested_negation() {
+        for input in [r"\+ \+ a(X)", r"\+ -1<X", r"\+ \+ \+ (a(X);b(X))"] {
+            let (leftover, parsed) = parse_body(input).unwrap();
+            assert_eq!(leftover, "");
+            assert_eq!(encode_joined(&parsed, AND), input);
+        }
+        let (_, parsed) = parse_body(r"\+ \+ a(X)").unwrap();
+        assert_eq!(
+            parsed,
+            vec![Goal::Not(Box::new(Goal::Not(Box::new(term("a")))))]
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_if_then() {
+        let (_, parsed) = parse_body(r"(a(X),b(X)->c(X))").unwrap();
+        assert_eq!(
+            parsed,
+            vec![Goal::IfThenElse {
+                condition: Box::new(Goal::Conjunction(vec![term("a"), term("b")])),
+                then: Box::new(term("c")),
+                otherwise: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_if_then_else_in_disjunction() {
+        let (_, parsed) = parse_body(r"(a(X);b(X)->c(X);d(X))").unwrap();
+        assert_eq!(
+            parsed,
+            vec![Goal::Disjunction(vec![
+                term("a"),
+                Goal::IfThenElse {
+                    condition: Box::new(term("b")),
+                    then: Box::new(term("c")),
+                    otherwise: Some(Box::new(term("d"))),
+                },
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_without_term() {
+        let goal = Goal::IfThenElse {
+            condition: Box::new(term("a")),
+            then: Box::new(Goal::Disjunction(vec![term("b"), term("c")])),
+            otherwise: Some(Box::new(Goal::Not(Box::new(term("c"))))),
+        };
+
+        assert_eq!(
+            goal.clone().without_term("c/1"),
+            Some(Goal::IfThenElse {
+                condition: Box::new(term("a")),
+                then: Box::new(term("b")),
+                otherwise: None,
+            })
+        );
+        // a call with a different arity is a call to a different term
+        assert_eq!(goal.clone().without_term("a/2"), Some(goal.clone()));
+        assert_eq!(goal.without_term("a/1"), None);
+    }
+}
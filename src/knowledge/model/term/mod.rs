@@ -1,18 +1,32 @@
+pub mod arg;
 pub mod args_binding;
 pub mod bound_term;
+pub mod built_in;
+pub mod goal;
 pub mod rule;
 
 use nom::{
-    bytes::complete::tag, character::complete::newline, error::VerboseError, multi::many0,
-    sequence::terminated, IResult,
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::multispace1,
+    combinator::{eof, map, peek, value},
+    error::{context, VerboseError},
+    multi::many0,
+    sequence::{preceded, terminated, tuple},
+    IResult,
 };
 
+use crate::knowledge::model::parsing::{clause_layout, layout};
+
 use {
     args_binding::ArgsBinding,
     bound_term::{parse_bound_term, BoundTerm},
-    rule::{parse_rule, Rule},
+    rule::{parse_named_rule, Rule},
 };
 
+#[cfg(test)]
+use {arg::Arg, goal::Goal};
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct Term {
     pub facts: Vec<ArgsBinding>,
@@ -22,9 +36,7 @@ pub struct Term {
 const NEWLINE: &str = r"
 ";
 const END_OF_CLAUSE: &str = r".";
-const END_OF_FACT: &str = r").
-";
-const END_OF_RULE_HEAD: &str = r"):-";
+const RULE_HEAD_SEPARATOR: &str = r":-";
 
 impl Term {
     pub fn new(facts: &[ArgsBinding], rules: &[Rule]) -> Self {
@@ -36,21 +48,18 @@ impl Term {
 
     pub fn encode(&self, term_name: &str) -> String {
         let mut encoded = String::new();
-        let term_name_prefix = term_name.to_owned() + "(";
 
         for arg_binding in &self.facts {
-            encoded.push_str(&term_name_prefix);
-            encoded.push_str(&arg_binding.encode());
-            encoded.push_str(END_OF_FACT);
+            encoded.push_str(&BoundTerm::new(term_name, arg_binding.clone()).encode());
+            encoded.push_str(END_OF_CLAUSE);
+            encoded.push_str(NEWLINE);
         }
 
         for rule in &self.rules {
-            encoded.push_str(&term_name_prefix);
-
-            encoded.push_str(&rule.head.encode());
-            encoded.push_str(END_OF_RULE_HEAD);
+            encoded.push_str(&BoundTerm::new(term_name, rule.head.clone()).encode());
+            encoded.push_str(RULE_HEAD_SEPARATOR);
 
-            let body_entries: Vec<String> = rule.body.iter().map(|b| b.encode()).collect();
+            let body_entries: Vec<String> = rule.body.iter().map(|g| g.encode()).collect();
             encoded.push_str(&body_entries.join(","));
             encoded.push_str(END_OF_CLAUSE);
             encoded.push_str(NEWLINE);
@@ -59,22 +68,69 @@ impl Term {
     }
 }
 
+// terms are told apart by name and arity - "parent/2" and "parent/3" are two different terms
+pub fn term_key(name: &str, arity: usize) -> String {
+    format!("{}/{}", name, arity)
+}
+
+// the inverse of term_key - a quoted name may contain "/" on its own, so the last one is the split
+pub fn split_term_key(key: &str) -> Option<(&str, usize)> {
+    let (name, arity) = key.rsplit_once('/')?;
+    Some((name, arity.parse().ok()?))
+}
+
+// a single fact or rule together with the name of the term it belongs to
+pub enum Clause {
+    Fact(BoundTerm),
+    Rule(String, Rule),
+}
+
+// facts and rules may come in any order and with any layout in between - they are still encoded
+// facts first
 pub fn parse_term(i: &str) -> IResult<&str, Term, VerboseError<&str>> {
-    let (leftover, facts) = many0(terminated(parse_fact, newline))(i)?;
-    let (leftover, rules) = many0(terminated(parse_rule, newline))(leftover)?;
+    let (leftover, clauses) = many0(preceded(clause_layout, parse_clause))(i)?;
+    let (leftover, _) = clause_layout(leftover)?;
 
-    Ok((
-        leftover,
-        Term {
-            facts: facts.into_iter().map(|f| f.arg_bindings).collect(),
-            rules,
-        },
-    ))
+    let mut term = Term::new(&[], &[]);
+    for clause in clauses {
+        match clause {
+            Clause::Fact(fact) => term.facts.push(fact.arg_bindings),
+            Clause::Rule(_, rule) => term.rules.push(rule),
+        }
+    }
+    Ok((leftover, term))
+}
+
+pub fn parse_clause(i: &str) -> IResult<&str, Clause, VerboseError<&str>> {
+    alt((
+        map(parse_fact, Clause::Fact),
+        map(parse_named_rule, |(name, rule)| Clause::Rule(name, rule)),
+    ))(i)
 }
 
 // parses "some_fact_name(SomeVar,someConst,_)."
 fn parse_fact(i: &str) -> IResult<&str, BoundTerm, VerboseError<&str>> {
-    terminated(parse_bound_term, tag("."))(i)
+    terminated(parse_bound_term, context("'.' or ':-'", end_of_clause))(i)
+}
+
+// the "." has to be followed by layout - otherwise it's not the end of the clause
+pub(crate) fn end_of_clause(i: &str) -> IResult<&str, (), VerboseError<&str>> {
+    value(
+        (),
+        tuple((
+            layout,
+            tag(END_OF_CLAUSE),
+            peek(alt((multispace1, eof, tag("%")))),
+        )),
+    )(i)
+}
+
+#[test]
+fn test_term_key() {
+    assert_eq!(term_key("parent", 2), "parent/2");
+    assert_eq!(split_term_key("parent/2"), Some(("parent", 2)));
+    assert_eq!(split_term_key("a/b/0"), Some(("a/b", 0)));
+    assert_eq!(split_term_key("parent"), None);
 }
 
 #[test]
@@ -87,7 +143,7 @@ fn test_parse_fact() {
             BoundTerm {
                 name: "parent".to_string(),
                 arg_bindings: ArgsBinding {
-                    binding: vec!["john".to_string(), "mary".to_string()]
+                    binding: vec![Arg::Atom("john".to_string()), Arg::Atom("mary".to_string())]
                 },
             }
         ))
@@ -109,29 +165,41 @@ parent(X,Y):-strong_match_in_dna(X,Y),older(X,Y).
             Term {
                 facts: vec![
                     ArgsBinding {
-                        binding: vec!["john".to_string(), "mary".to_string()]
+                        binding: vec![Arg::Atom("john".to_string()), Arg::Atom("mary".to_string())]
                     },
                     ArgsBinding {
-                        binding: vec!["bill".to_string(), "hilly".to_string()]
+                        binding: vec![
+                            Arg::Atom("bill".to_string()),
+                            Arg::Atom("hilly".to_string())
+                        ]
                     }
                 ],
                 rules: vec![Rule {
                     head: ArgsBinding {
-                        binding: vec!["X".to_string(), "Y".to_string()],
+                        binding: vec![
+                            Arg::Variable("X".to_string()),
+                            Arg::Variable("Y".to_string())
+                        ],
                     },
                     body: vec![
-                        BoundTerm {
+                        Goal::Term(BoundTerm {
                             name: "strong_match_in_dna".to_string(),
                             arg_bindings: ArgsBinding {
-                                binding: vec!["X".to_string(), "Y".to_string()]
+                                binding: vec![
+                                    Arg::Variable("X".to_string()),
+                                    Arg::Variable("Y".to_string())
+                                ]
                             }
-                        },
-                        BoundTerm {
+                        }),
+                        Goal::Term(BoundTerm {
                             name: "older".to_string(),
                             arg_bindings: ArgsBinding {
-                                binding: vec!["X".to_string(), "Y".to_string()]
+                                binding: vec![
+                                    Arg::Variable("X".to_string()),
+                                    Arg::Variable("Y".to_string())
+                                ]
                             }
-                        }
+                        })
                     ]
                 }],
             }
@@ -144,29 +212,41 @@ fn test_encode_term() {
     let term = Term {
         facts: vec![
             ArgsBinding {
-                binding: vec!["john".to_string(), "mary".to_string()],
+                binding: vec![Arg::Atom("john".to_string()), Arg::Atom("mary".to_string())],
             },
             ArgsBinding {
-                binding: vec!["bill".to_string(), "hilly".to_string()],
+                binding: vec![
+                    Arg::Atom("bill".to_string()),
+                    Arg::Atom("hilly".to_string()),
+                ],
             },
         ],
         rules: vec![Rule {
             head: ArgsBinding {
-                binding: vec!["X".to_string(), "Y".to_string()],
+                binding: vec![
+                    Arg::Variable("X".to_string()),
+                    Arg::Variable("Y".to_string()),
+                ],
             },
             body: vec![
-                BoundTerm {
+                Goal::Term(BoundTerm {
                     name: "strong_match_in_dna".to_string(),
                     arg_bindings: ArgsBinding {
-                        binding: vec!["X".to_string(), "Y".to_string()],
+                        binding: vec![
+                            Arg::Variable("X".to_string()),
+                            Arg::Variable("Y".to_string()),
+                        ],
                     },
-                },
-                BoundTerm {
+                }),
+                Goal::Term(BoundTerm {
                     name: "older".to_string(),
                     arg_bindings: ArgsBinding {
-                        binding: vec!["X".to_string(), "Y".to_string()],
+                        binding: vec![
+                            Arg::Variable("X".to_string()),
+                            Arg::Variable("Y".to_string()),
+                        ],
                     },
-                },
+                }),
             ],
         }],
     };
@@ -179,3 +259,63 @@ parent(X,Y):-strong_match_in_dna(X,Y),older(X,Y).
 "
     );
 }
+
+#[test]
+fn test_parse_encode_structured_args() {
+    let input = r#"owns(bob,car(red,2010)).
+owns(alice,[bike,"old, rusty"]).
+owns(X,Y):-bought(X,Y,[_|_]).
+"#;
+    let (leftover, term) = parse_term(input).unwrap();
+    assert_eq!(leftover, "");
+    assert_eq!(
+        term.facts[0],
+        ArgsBinding::new(&[
+            Arg::Atom("bob".to_string()),
+            Arg::Compound(
+                "car".to_string(),
+                ArgsBinding::new(&[Arg::Atom("red".to_string()), Arg::Integer(2010)])
+            ),
+        ])
+    );
+    assert_eq!(
+        term.facts[1].binding[1],
+        Arg::List(
+            vec![
+                Arg::Atom("bike".to_string()),
+                Arg::String("old, rusty".to_string())
+            ],
+            None
+        )
+    );
+    assert_eq!(term.encode("owns"), input);
+}
+
+#[test]
+fn test_parse_encode_zero_arity_term() {
+    let input = r"raining.
+raining:-debug_mode,forecast(rain).
+";
+    let (leftover, term) = parse_term(input).unwrap();
+    assert_eq!(leftover, "");
+    assert_eq!(
+        term,
+        Term {
+            facts: vec![ArgsBinding { binding: vec![] }],
+            rules: vec![Rule {
+                head: ArgsBinding { binding: vec![] },
+                body: vec![
+                    Goal::Term(BoundTerm::new(
+                        "debug_mode",
+                        ArgsBinding { binding: vec![] }
+                    )),
+                    Goal::Term(BoundTerm::new(
+                        "forecast",
+                        ArgsBinding::new(&[Arg::Atom("rain".to_string())])
+                    )),
+                ],
+            }],
+        }
+    );
+    assert_eq!(term.encode("raining"), input);
+}
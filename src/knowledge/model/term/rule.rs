@@ -1,37 +1,68 @@
 use nom::{
     bytes::complete::tag,
-    error::VerboseError,
-    multi::separated_list1,
+    combinator::map,
+    error::{context, VerboseError},
     sequence::{separated_pair, terminated},
     IResult,
 };
 
+use crate::knowledge::model::parsing::padded;
+
 use super::{
     args_binding::ArgsBinding,
     bound_term::{parse_bound_term, BoundTerm},
+    end_of_clause,
+    goal::{parse_body, Goal},
+    RULE_HEAD_SEPARATOR,
 };
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub struct Rule {
     pub head: ArgsBinding,
-    pub body: Vec<BoundTerm>,
+    pub body: Vec<Goal>,
+}
+
+impl Rule {
+    // all the terms called in the body, including the ones nested in negations, disjunctions and
+    // if-then-elses
+    pub fn body_terms(&self) -> Vec<&BoundTerm> {
+        self.body.iter().flat_map(|g| g.bound_terms()).collect()
+    }
+
+    pub fn body_terms_mut(&mut self) -> Vec<&mut BoundTerm> {
+        self.body
+            .iter_mut()
+            .flat_map(|g| g.bound_terms_mut())
+            .collect()
+    }
 }
 
-// parses "some_rule_name(SomeVar,someConst,_):-some_fact(SomeVar),some_rule(someConst,SomeVar)."
+// parses "some_rule_name(SomeVar,someConst,_):-some_fact(SomeVar),\+some_rule(someConst,SomeVar)."
 pub fn parse_rule(i: &str) -> IResult<&str, Rule, VerboseError<&str>> {
+    map(parse_named_rule, |(_, rule)| rule)(i)
+}
+
+// same as parse_rule, but keeps the name of the head
+pub(crate) fn parse_named_rule(i: &str) -> IResult<&str, (String, Rule), VerboseError<&str>> {
     let raw_rule = separated_pair(
         parse_bound_term,
-        tag(":-"),
-        terminated(separated_list1(tag(","), parse_bound_term), tag(".")),
+        context("'.' or ':-'", padded(tag(RULE_HEAD_SEPARATOR))),
+        terminated(
+            parse_body,
+            context("',' or '.' after a goal", end_of_clause),
+        ),
     )(i);
 
     raw_rule.map(|(leftover, (head, body))| {
         (
             leftover,
-            Rule {
-                head: head.arg_bindings,
-                body,
-            },
+            (
+                head.name,
+                Rule {
+                    head: head.arg_bindings,
+                    body,
+                },
+            ),
         )
     })
 }
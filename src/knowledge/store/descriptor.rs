@@ -0,0 +1,211 @@
+use bincode::{config, decode_from_slice, encode_to_vec, Encode};
+use bincode_derive::Decode;
+use thiserror::Error;
+
+// The on-disk format of the descriptor - the index of the terms in page.pl. It starts with a
+// header carrying the format version, older versions are migrated on load. Descriptors written
+// before the header existed are version 1.
+//
+// Bumping the version: add the new stored structs, a migrate_vN step from the previous version
+// and a match arm in decode()
+
+pub(crate) const VERSION: u32 = 2;
+
+const MAGIC: &[u8; 4] = b"ILDS";
+const HEADER_LEN: usize = MAGIC.len() + size_of::<u32>();
+
+#[derive(Error, Debug)]
+pub(crate) enum DecodeError {
+    // written by a newer build - rebuilding it would drop whatever the newer format knows
+    #[error("the descriptor is version {0}, but only up to version {VERSION} can be read")]
+    UnsupportedVersion(u32),
+    #[error("corrupted descriptor: {0}")]
+    Corrupted(#[from] bincode::error::DecodeError),
+}
+
+#[derive(Clone)]
+pub(crate) struct DescriptorEntry {
+    pub(crate) name: String,
+    pub(crate) offset: usize,
+    pub(crate) len: usize,
+    // deleted entries are kept until the next load, so that the indexes of the rest stay put -
+    // they are never written
+    pub(crate) is_deleted: bool,
+}
+
+pub(crate) struct Descriptor {
+    pub(crate) entries: Vec<DescriptorEntry>,
+    // of page.pl at the time of writing - a match means the entries can be trusted as they are.
+    // Migrated descriptors don't have one
+    pub(crate) page_checksum: Option<u64>,
+    // the version it was read as
+    pub(crate) version: u32,
+}
+
+// version 2
+#[derive(Decode, Encode)]
+struct StoredDescriptor {
+    page_checksum: u64,
+    entries: Vec<StoredEntry>,
+}
+
+#[derive(Decode, Encode)]
+struct StoredEntry {
+    name: String,
+    offset: u64,
+    len: u64,
+}
+
+// version 1 - no header, deleted entries included
+#[derive(Decode, Encode)]
+struct StoredEntryV1 {
+    name: String,
+    offset: usize,
+    len: usize,
+    is_deleted: bool,
+}
+
+pub(crate) fn encode(entries: &[DescriptorEntry], page: &str) -> Vec<u8> {
+    let stored = StoredDescriptor {
+        page_checksum: checksum(page),
+        entries: entries
+            .iter()
+            .filter(|entry| !entry.is_deleted)
+            .map(|entry| StoredEntry {
+                name: entry.name.clone(),
+                offset: entry.offset as u64,
+                len: entry.len as u64,
+            })
+            .collect(),
+    };
+    let mut encoded = Vec::from(&MAGIC[..]);
+    encoded.extend_from_slice(&VERSION.to_le_bytes());
+    encoded
+        .extend(encode_to_vec(stored, config::standard()).expect("encoding to a vec doesn't fail"));
+    encoded
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Result<Descriptor, DecodeError> {
+    let Some(body) = bytes.strip_prefix(&MAGIC[..]) else {
+        let (entries, _): (Vec<StoredEntryV1>, _) = decode_from_slice(bytes, config::standard())?;
+        return Ok(migrate_v1(entries));
+    };
+    let (version, body) = body.split_at_checked(size_of::<u32>()).ok_or_else(|| {
+        bincode::error::DecodeError::UnexpectedEnd {
+            additional: HEADER_LEN - bytes.len(),
+        }
+    })?;
+    let version = u32::from_le_bytes(version.try_into().expect("split at the size of a u32"));
+    match version {
+        VERSION => {
+            let (stored, _): (StoredDescriptor, _) = decode_from_slice(body, config::standard())?;
+            Ok(Descriptor {
+                entries: stored
+                    .entries
+                    .into_iter()
+                    .map(|entry| DescriptorEntry {
+                        name: entry.name,
+                        offset: entry.offset as usize,
+                        len: entry.len as usize,
+                        is_deleted: false,
+                    })
+                    .collect(),
+                page_checksum: Some(stored.page_checksum),
+                version,
+            })
+        }
+        _ => Err(DecodeError::UnsupportedVersion(version)),
+    }
+}
+
+fn migrate_v1(entries: Vec<StoredEntryV1>) -> Descriptor {
+    Descriptor {
+        entries: entries
+            .into_iter()
+            .filter(|entry| !entry.is_deleted)
+            .map(|entry| DescriptorEntry {
+                name: entry.name,
+                offset: entry.offset,
+                len: entry.len,
+                is_deleted: false,
+            })
+            .collect(),
+        page_checksum: None,
+        version: 1,
+    }
+}
+
+// FNV-1a - only meant to notice that page.pl changed behind the descriptor's back
+pub(crate) fn checksum(page: &str) -> u64 {
+    page.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, offset: usize, len: usize, is_deleted: bool) -> DescriptorEntry {
+        DescriptorEntry {
+            name: name.to_string(),
+            offset,
+            len,
+            is_deleted,
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let entries = [
+            entry("first/1", 0, 10, false),
+            entry("", 0, 0, true),
+            entry("second/2", 10, 5, false),
+        ];
+        let decoded = decode(&encode(&entries, "some page")).unwrap();
+
+        assert_eq!(decoded.version, VERSION);
+        assert_eq!(decoded.page_checksum, Some(checksum("some page")));
+        let names: Vec<&str> = decoded.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["first/1", "second/2"]);
+        assert_eq!((decoded.entries[1].offset, decoded.entries[1].len), (10, 5));
+    }
+
+    #[test]
+    fn test_migrate_v1() {
+        let v1 = vec![
+            StoredEntryV1 {
+                name: "first".to_string(),
+                offset: 0,
+                len: 10,
+                is_deleted: false,
+            },
+            StoredEntryV1 {
+                name: "".to_string(),
+                offset: 0,
+                len: 0,
+                is_deleted: true,
+            },
+        ];
+        let decoded = decode(&encode_to_vec(v1, config::standard()).unwrap()).unwrap();
+
+        assert_eq!(decoded.version, 1);
+        assert_eq!(decoded.page_checksum, None);
+        assert_eq!(decoded.entries.len(), 1);
+        assert_eq!(decoded.entries[0].name, "first");
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let mut encoded = encode(&[], "");
+        encoded[MAGIC.len()..HEADER_LEN].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            decode(&encoded),
+            Err(DecodeError::UnsupportedVersion(v)) if v == VERSION + 1
+        ));
+        assert!(matches!(
+            decode(&encoded[..MAGIC.len() + 1]),
+            Err(DecodeError::Corrupted(_))
+        ));
+    }
+}
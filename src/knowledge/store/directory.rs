@@ -0,0 +1,494 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use scryer_prolog::{Machine, MachineBuilder};
+
+use crate::knowledge::model::fat_term::{parse_fat_term, FatTerm};
+use crate::knowledge::model::parsing::ParseError;
+use crate::knowledge::model::term::{bound_term::BoundTerm, split_term_key};
+use crate::knowledge::store::{
+    engine,
+    persistent::{
+        check_unique, rebuild_descriptor, sync_dir, temp_path, write_synced, TEMP_SUFFIX,
+    },
+    Consult, Delete, Error, Get, Keys, Load, Put, Staged, TermsStore, Transact, Transaction,
+    DESCRIPTOR_NAME, PAGE_NAME,
+};
+
+// A store that keeps every term in its own "<name>.<arity>.pl" file in the module directory, so
+// that a change to a term is a change to its file only and git can diff and merge modules term
+// by term. There is no index - the files are read on load and the key of a term is taken from its
+// content, so files can be added, renamed or edited by hand. A file whose term has no key, or
+// whose term is in another file too, is kept under its file name and can't be read until it's
+// fixed by hand
+//
+// A module uses it when it has a LAYOUT_MARKER file. The first load of such a module splits its
+// page.pl into files and removes page.pl and the descriptor
+
+pub const LAYOUT_MARKER: &str = ".one_file_per_term";
+
+const EXTENSION: &str = "pl";
+
+pub struct DirectoryTerms {
+    base_path: PathBuf,
+    // "name/arity" -> the file of the term, or the file name for the files in `unkeyed`
+    files: HashMap<String, PathBuf>,
+    // file name -> the key its term has in another file too - None when the term has no key
+    unkeyed: HashMap<String, Option<String>>,
+    // sorted
+    keys: Vec<String>,
+    // built on the first consult - every file has to be read for it
+    engine: Option<Machine>,
+    // set by every change - the engine is reloaded on the next consult
+    engine_is_stale: bool,
+}
+
+impl DirectoryTerms {
+    pub fn is_used_by(module_path: &Path) -> bool {
+        module_path.join(LAYOUT_MARKER).is_file()
+    }
+
+    pub fn new(path: &Path) -> Result<Self, Error> {
+        let mut terms = Self {
+            base_path: path.to_owned(),
+            files: HashMap::new(),
+            unkeyed: HashMap::new(),
+            keys: vec![],
+            engine: None,
+            engine_is_stale: true,
+        };
+        let stale_temp_suffix = format!(".{}{}", EXTENSION, TEMP_SUFFIX);
+        // "name/arity" -> the files with the term, by their names
+        let mut by_key: HashMap<String, Vec<(String, PathBuf)>> = HashMap::new();
+        for dir_entry in fs::read_dir(path)? {
+            let file_path = dir_entry?.path();
+            let file_name = file_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            // left by a commit that didn't get to its renames - the files it was for are as they
+            // were before it
+            if file_name.ends_with(&stale_temp_suffix) && file_path.is_file() {
+                fs::remove_file(&file_path)?;
+                continue;
+            }
+            let is_term_file = file_path.extension().is_some_and(|ext| ext == EXTENSION)
+                && file_name != PAGE_NAME
+                && file_path.is_file();
+            if !is_term_file {
+                continue;
+            }
+            match raw_term_key(&fs::read_to_string(&file_path)?) {
+                Some(term_key) => by_key
+                    .entry(term_key)
+                    .or_default()
+                    .push((file_name, file_path)),
+                None => {
+                    terms.unkeyed.insert(file_name.clone(), None);
+                    terms.files.insert(file_name, file_path);
+                }
+            }
+        }
+        for (term_key, mut file_paths) in by_key {
+            if file_paths.len() == 1 {
+                terms.files.insert(term_key, file_paths.remove(0).1);
+                continue;
+            }
+            // neither file is the term - every one of them is left to be fixed by hand
+            for (file_name, file_path) in file_paths {
+                terms
+                    .unkeyed
+                    .insert(file_name.clone(), Some(term_key.clone()));
+                terms.files.insert(file_name, file_path);
+            }
+        }
+
+        let page_path = path.join(PAGE_NAME);
+        if terms.files.is_empty() && page_path.is_file() {
+            terms.split_page(&fs::read_to_string(&page_path)?)?;
+            fs::remove_file(page_path)?;
+            let descriptor_path = path.join(DESCRIPTOR_NAME);
+            if descriptor_path.exists() {
+                fs::remove_file(descriptor_path)?;
+            }
+            sync_dir(path)?;
+        }
+
+        terms.keys = terms.files.keys().cloned().collect();
+        terms.keys.sort();
+        Ok(terms)
+    }
+
+    // every term of page.pl goes in its own file, as it is - terms that don't parse included
+    fn split_page(&mut self, page: &str) -> Result<(), Error> {
+        let descriptor = rebuild_descriptor(page);
+        check_unique(&descriptor)?;
+        for entry in descriptor {
+            let file_path = self.free_path(&self.files, &entry.name);
+            write_file(
+                &file_path,
+                &page.as_bytes()[entry.offset..entry.offset + entry.len],
+            )?;
+            self.files.insert(entry.name, file_path);
+        }
+        Ok(())
+    }
+
+    // "<name>.<arity>.pl", with anything that doesn't belong in a file name replaced - a number
+    // is added when another term has the same file
+    fn free_path(&self, files: &HashMap<String, PathBuf>, term_key: &str) -> PathBuf {
+        let (name, arity) = split_term_key(term_key).unwrap_or((term_key, 0));
+        let name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let taken: HashSet<&PathBuf> = files
+            .iter()
+            .filter(|(key, _)| *key != term_key)
+            .map(|(_, path)| path)
+            .collect();
+        let mut file_path = self
+            .base_path
+            .join(format!("{}.{}.{}", name, arity, EXTENSION));
+        let mut suffix = 1;
+        while taken.contains(&file_path) {
+            file_path = self
+                .base_path
+                .join(format!("{}.{}.{}.{}", name, arity, suffix, EXTENSION));
+            suffix += 1;
+        }
+        file_path
+    }
+
+    // the terms one after the other, the way page.pl would have them
+    fn encode(&self) -> io::Result<String> {
+        let mut knowledge = String::new();
+        for term_key in &self.keys {
+            knowledge.push_str(&fs::read_to_string(&self.files[term_key])?);
+        }
+        Ok(knowledge)
+    }
+}
+
+// the key from the content of a term file - the same way the persistent store keys the terms of
+// a page.pl that has no descriptor. None when the file has no term header
+fn raw_term_key(content: &str) -> Option<String> {
+    rebuild_descriptor(content)
+        .into_iter()
+        .next()
+        .map(|entry| entry.name)
+        .filter(|term_key| split_term_key(term_key).is_some_and(|(name, _)| !name.is_empty()))
+}
+
+fn write_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    let temp_path = temp_path(path);
+    write_synced(&temp_path, |file| file.write_all(content))?;
+    fs::rename(temp_path, path)
+}
+
+impl Get for DirectoryTerms {
+    fn get(&self, term_key: &str) -> Result<Option<FatTerm>, Error> {
+        let Some(file_path) = self.files.get(term_key) else {
+            return Ok(None);
+        };
+        let content = fs::read_to_string(file_path)?;
+        match self.unkeyed.get(term_key) {
+            Some(Some(duplicated_key)) => Err(Error::Duplicate(duplicated_key.clone())),
+            Some(None) => {
+                parse_fat_term(&content)?;
+                // parsed, but to a term without a name
+                let mut parse_error = ParseError::at(&content, 0);
+                parse_error.expected = "a term header".to_owned();
+                Err(parse_error.into())
+            }
+            None => {
+                let (_, fat_term) = parse_fat_term(&content)?;
+                Ok(Some(fat_term))
+            }
+        }
+    }
+}
+
+impl Put for DirectoryTerms {
+    fn put(&mut self, term_key: &str, term: FatTerm) -> Result<(), Error> {
+        let mut transaction = self.begin();
+        transaction.put(term_key, term);
+        self.commit(transaction)
+    }
+}
+
+impl Delete for DirectoryTerms {
+    fn delete(&mut self, term_key: &str) -> Result<(), Error> {
+        let mut transaction = self.begin();
+        transaction.delete(term_key);
+        self.commit(transaction)
+    }
+}
+
+impl Transact for DirectoryTerms {
+    // the changes are worked out first and every new content is written to a temp file - only
+    // renames and removes are left once a file of the module is touched. When one of those fails,
+    // the files already touched are put back - only a crash in between can leave part of the
+    // commit applied. A renamed term is written to its new file and its old file is removed,
+    // which git takes for a move
+    fn commit(&mut self, transaction: Transaction) -> Result<(), Error> {
+        let mut files = self.files.clone();
+        let mut written: HashMap<PathBuf, String> = HashMap::new();
+        let mut removed: HashSet<PathBuf> = HashSet::new();
+
+        for change in transaction.changes {
+            match change {
+                Staged::Put(term_key, term) => {
+                    let new_key = term.key();
+                    if new_key != term_key && files.contains_key(&new_key) {
+                        return Err(Error::AlreadyPresent(new_key));
+                    }
+                    if self.unkeyed.values().flatten().any(|key| *key == new_key) {
+                        return Err(Error::Duplicate(new_key));
+                    }
+                    let file_path = match files.remove(&term_key) {
+                        Some(old_path) if new_key == term_key => old_path,
+                        Some(old_path) => {
+                            written.remove(&old_path);
+                            removed.insert(old_path);
+                            self.free_path(&files, &new_key)
+                        }
+                        None => self.free_path(&files, &new_key),
+                    };
+                    removed.remove(&file_path);
+                    written.insert(file_path.clone(), term.encode());
+                    files.insert(new_key, file_path);
+                }
+                Staged::Delete(term_key) => {
+                    let file_path = files.remove(&term_key).ok_or(Error::NotFound(term_key))?;
+                    written.remove(&file_path);
+                    removed.insert(file_path);
+                }
+            }
+        }
+
+        // what the touched files held before - None for the ones the commit creates
+        let mut previous: Vec<(&PathBuf, Option<Vec<u8>>)> = vec![];
+        for file_path in written.keys().chain(removed.iter()) {
+            match fs::read(file_path) {
+                Ok(content) => previous.push((file_path, Some(content))),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    previous.push((file_path, None))
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let mut temp_paths = vec![];
+        for (file_path, content) in &written {
+            let temp_path = temp_path(file_path);
+            let staged = write_synced(&temp_path, |file| file.write_all(content.as_bytes()));
+            temp_paths.push((file_path, temp_path));
+            if let Err(err) = staged {
+                for (_, temp_path) in &temp_paths {
+                    let _ = fs::remove_file(temp_path);
+                }
+                return Err(err.into());
+            }
+        }
+        let applied = temp_paths
+            .iter()
+            .try_for_each(|(file_path, temp_path)| fs::rename(temp_path, file_path))
+            .and_then(|_| removed.iter().try_for_each(fs::remove_file));
+        if let Err(err) = applied {
+            // the files already renamed or removed are put back as they were
+            for (file_path, content) in previous {
+                let _ = match content {
+                    Some(content) => write_file(file_path, &content),
+                    None if file_path.is_file() => fs::remove_file(file_path),
+                    None => Ok(()),
+                };
+            }
+            for (_, temp_path) in &temp_paths {
+                let _ = fs::remove_file(temp_path);
+            }
+            return Err(err.into());
+        }
+        sync_dir(&self.base_path)?;
+
+        self.keys = files.keys().cloned().collect();
+        self.keys.sort();
+        // the last file left of a duplicate stays under its file name until the next load
+        self.unkeyed
+            .retain(|file_name, _| files.contains_key(file_name));
+        self.files = files;
+        self.engine_is_stale = true;
+        Ok(())
+    }
+}
+
+impl Keys for DirectoryTerms {
+    fn keys(&self) -> &Vec<String> {
+        &self.keys
+    }
+}
+
+impl Load for DirectoryTerms {
+    type Store = DirectoryTerms;
+
+    fn load(path: &Path) -> Result<Self::Store, Error> {
+        DirectoryTerms::new(path)
+    }
+}
+
+impl Consult for DirectoryTerms {
+    fn consult(&mut self, term: &BoundTerm) -> Result<Vec<HashMap<String, String>>, Error> {
+        let knowledge = if self.engine_is_stale {
+            Some(self.encode()?)
+        } else {
+            None
+        };
+        let engine = self
+            .engine
+            .get_or_insert_with(|| MachineBuilder::default().build());
+        if let Some(knowledge) = knowledge {
+            engine.load_module_string("knowledge", knowledge);
+            self.engine_is_stale = false;
+        }
+        engine::consult(engine, term)
+    }
+}
+
+impl TermsStore for DirectoryTerms {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge::store::test_utils::{knowledge_dir, term};
+
+    fn file_names(path: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_split_rename_delete() {
+        let path = knowledge_dir("directory");
+        fs::write(path.join(LAYOUT_MARKER), "").unwrap();
+        fs::write(
+            path.join(PAGE_NAME),
+            term("second").encode() + &term("first").encode(),
+        )
+        .unwrap();
+        assert!(DirectoryTerms::is_used_by(&path));
+
+        let mut terms = DirectoryTerms::new(&path).unwrap();
+        assert_eq!(
+            file_names(&path),
+            vec![LAYOUT_MARKER, "first.1.pl", "second.1.pl"]
+        );
+        assert_eq!(
+            fs::read_to_string(path.join("first.1.pl")).unwrap(),
+            term("first").encode()
+        );
+
+        terms.put("first/1", term("renamed")).unwrap();
+        terms.put("third/1", term("third")).unwrap();
+        assert!(matches!(
+            terms.put("third/1", term("second")),
+            Err(Error::AlreadyPresent(_))
+        ));
+        terms.delete("second/1").unwrap();
+        assert_eq!(
+            file_names(&path),
+            vec![LAYOUT_MARKER, "renamed.1.pl", "third.1.pl"]
+        );
+
+        let terms = DirectoryTerms::new(&path).unwrap();
+        assert_eq!(
+            terms.keys(),
+            &vec!["renamed/1".to_string(), "third/1".to_string()]
+        );
+        assert_eq!(terms.get("renamed/1").unwrap(), Some(term("renamed")));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_unkeyed_files() {
+        let path = knowledge_dir("directory_unkeyed");
+        fs::write(path.join(LAYOUT_MARKER), "").unwrap();
+        fs::write(path.join("first.1.pl"), term("first").encode()).unwrap();
+        fs::write(path.join("copy.pl"), term("first").encode()).unwrap();
+        fs::write(path.join("no_header.pl"), "first(a).\n").unwrap();
+        fs::write(path.join("second.1.pl"), term("second").encode()).unwrap();
+        fs::write(path.join("second.1.pl.tmp"), term("stale").encode()).unwrap();
+
+        let mut terms = DirectoryTerms::new(&path).unwrap();
+        assert_eq!(
+            terms.keys(),
+            &vec![
+                "copy.pl".to_string(),
+                "first.1.pl".to_string(),
+                "no_header.pl".to_string(),
+                "second/1".to_string()
+            ]
+        );
+        assert!(matches!(terms.get("copy.pl"), Err(Error::Duplicate(key)) if key == "first/1"));
+        assert!(matches!(terms.get("no_header.pl"), Err(Error::Parse(_))));
+        assert_eq!(terms.get("first/1").unwrap(), None);
+        assert!(!path.join("second.1.pl.tmp").exists());
+
+        assert!(matches!(
+            terms.put("second/1", term("first")),
+            Err(Error::Duplicate(_))
+        ));
+        terms.delete("copy.pl").unwrap();
+        terms.delete("no_header.pl").unwrap();
+        let terms = DirectoryTerms::new(&path).unwrap();
+        assert_eq!(
+            terms.keys(),
+            &vec!["first/1".to_string(), "second/1".to_string()]
+        );
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_commit_is_rolled_back() {
+        let path = knowledge_dir("directory_rollback");
+        fs::write(path.join(LAYOUT_MARKER), "").unwrap();
+        fs::write(path.join("first.1.pl"), term("first").encode()).unwrap();
+        fs::write(path.join("second.1.pl"), term("second").encode()).unwrap();
+        let mut terms = DirectoryTerms::new(&path).unwrap();
+
+        // a directory in the way of the new term's file makes its rename fail
+        fs::create_dir_all(path.join("third.1.pl").join("taken")).unwrap();
+        let mut transaction = terms.begin();
+        transaction.put("first/1", term("renamed"));
+        transaction.delete("second/1");
+        transaction.put("third/1", term("third"));
+        assert!(matches!(terms.commit(transaction), Err(Error::Io(_))));
+
+        assert_eq!(
+            file_names(&path),
+            vec![LAYOUT_MARKER, "first.1.pl", "second.1.pl", "third.1.pl"]
+        );
+        assert_eq!(
+            fs::read_to_string(path.join("first.1.pl")).unwrap(),
+            term("first").encode()
+        );
+        assert_eq!(
+            terms.keys(),
+            &vec!["first/1".to_string(), "second/1".to_string()]
+        );
+        assert_eq!(terms.get("second/1").unwrap(), Some(term("second")));
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
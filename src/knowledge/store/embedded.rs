@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use redb::{Database, ReadableTable, Table, TableDefinition};
+use scryer_prolog::{Machine, MachineBuilder};
+
+use crate::knowledge::model::fat_term::{parse_fat_term, FatTerm};
+use crate::knowledge::model::term::bound_term::BoundTerm;
+use crate::knowledge::store::{
+    engine,
+    persistent::{check_unique, rebuild_descriptor, sync_dir, temp_path, write_synced},
+    Consult, Delete, Error, Get, Keys, Load, Put, Staged, TermsStore, Transact, Transaction,
+    DATABASE_NAME, PAGE_NAME,
+};
+
+// A store for knowledge bases too big for a single page.pl - every term is a row under its key,
+// so a change costs the same no matter how many terms there are and a get parses only the term
+// it asks for. page.pl is written only on export
+//
+// A new database is filled from the page.pl next to it, if there is one
+
+// "name/arity" -> the term as it would be in page.pl. Terms that didn't parse on import are kept
+// as they were, under the key that rebuilding the descriptor gives them
+const TERMS: TableDefinition<'static, &str, &str> = TableDefinition::new("terms");
+
+pub struct EmbeddedTerms {
+    db: Database,
+    base_path: PathBuf,
+    // sorted - kept in step with the table after every commit
+    keys: Vec<String>,
+    // built on the first consult - the whole knowledge has to be read for it
+    engine: Option<Machine>,
+    // set by every change - the engine is reloaded on the next consult
+    engine_is_stale: bool,
+}
+
+// what a commit did to the keys - applied to `keys` only once the commit succeeds
+enum KeyChange {
+    Removed(String),
+    Added(String),
+}
+
+impl EmbeddedTerms {
+    pub fn new(path: &Path) -> Result<Self, Error> {
+        let db_path = path.join(DATABASE_NAME);
+        if !db_path.exists() {
+            // filled under a temp name and moved in only once the import is committed - a failed
+            // import leaves no database behind, so page.pl is imported again on the next load
+            let db_temp_path = temp_path(&db_path);
+            if let Err(err) = import(&db_temp_path, &path.join(PAGE_NAME)) {
+                let _ = fs::remove_file(&db_temp_path);
+                return Err(err);
+            }
+            fs::rename(&db_temp_path, &db_path)?;
+            sync_dir(path)?;
+        }
+        let db = Database::create(&db_path).map_err(redb::Error::from)?;
+
+        let read = db.begin_read().map_err(redb::Error::from)?;
+        let table = read.open_table(TERMS).map_err(redb::Error::from)?;
+        let mut keys = vec![];
+        for row in table.iter().map_err(redb::Error::from)? {
+            let (key, _) = row.map_err(redb::Error::from)?;
+            keys.push(key.value().to_owned());
+        }
+
+        Ok(Self {
+            db,
+            base_path: path.to_owned(),
+            keys,
+            engine: None,
+            engine_is_stale: true,
+        })
+    }
+
+    // writes page.pl next to the database - the persistent store rebuilds its descriptor from it
+    // on the next load. Terms are in the order of their keys
+    pub fn export(&self) -> Result<(), Error> {
+        let page_path = self.base_path.join(PAGE_NAME);
+        let page_temp_path = temp_path(&page_path);
+        let page = self.encode()?;
+
+        write_synced(&page_temp_path, |file| file.write_all(page.as_bytes()))?;
+        fs::rename(&page_temp_path, &page_path)?;
+        Ok(sync_dir(&self.base_path)?)
+    }
+
+    // the terms one after the other, the way page.pl has them
+    fn encode(&self) -> Result<String, Error> {
+        let read = self.db.begin_read().map_err(redb::Error::from)?;
+        let table = read.open_table(TERMS).map_err(redb::Error::from)?;
+        let mut page = String::new();
+        for row in table.iter().map_err(redb::Error::from)? {
+            let (_, raw_term) = row.map_err(redb::Error::from)?;
+            page.push_str(raw_term.value());
+        }
+        Ok(page)
+    }
+
+    fn apply(&mut self, key_changes: Vec<KeyChange>) {
+        for key_change in key_changes {
+            match key_change {
+                KeyChange::Removed(key) => {
+                    if let Ok(idx) = self.keys.binary_search(&key) {
+                        self.keys.remove(idx);
+                    }
+                }
+                KeyChange::Added(key) => {
+                    if let Err(idx) = self.keys.binary_search(&key) {
+                        self.keys.insert(idx, key);
+                    }
+                }
+            }
+        }
+        self.engine_is_stale = true;
+    }
+}
+
+// a new database with the terms of page.pl, if there is one - the table is created either way
+fn import(db_path: &Path, page_path: &Path) -> Result<(), Error> {
+    // left by an import that didn't finish
+    if db_path.exists() {
+        fs::remove_file(db_path)?;
+    }
+    let db = Database::create(db_path).map_err(redb::Error::from)?;
+    let write = db.begin_write().map_err(redb::Error::from)?;
+    {
+        // opening the table in a write transaction creates it
+        let mut table = write.open_table(TERMS).map_err(redb::Error::from)?;
+        if page_path.exists() {
+            let page = fs::read_to_string(page_path)?;
+            let descriptor = rebuild_descriptor(&page);
+            check_unique(&descriptor)?;
+            for entry in descriptor {
+                let raw_term = &page[entry.offset..entry.offset + entry.len];
+                table
+                    .insert(entry.name.as_str(), raw_term)
+                    .map_err(redb::Error::from)?;
+            }
+        }
+    }
+    write.commit().map_err(redb::Error::from)?;
+    Ok(())
+}
+
+// the changes are staged in a single write transaction - nothing is visible until it's committed
+// and dropping it aborts all of them
+fn stage(
+    table: &mut Table<'_, &str, &str>,
+    change: Staged,
+    key_changes: &mut Vec<KeyChange>,
+) -> Result<(), Error> {
+    match change {
+        Staged::Put(term_key, term) => {
+            let new_key = term.key();
+            if new_key != term_key
+                && table
+                    .get(new_key.as_str())
+                    .map_err(redb::Error::from)?
+                    .is_some()
+            {
+                return Err(Error::AlreadyPresent(new_key));
+            }
+            if table
+                .remove(term_key.as_str())
+                .map_err(redb::Error::from)?
+                .is_some()
+            {
+                key_changes.push(KeyChange::Removed(term_key));
+            }
+            table
+                .insert(new_key.as_str(), term.encode().as_str())
+                .map_err(redb::Error::from)?;
+            key_changes.push(KeyChange::Added(new_key));
+        }
+        Staged::Delete(term_key) => {
+            if table
+                .remove(term_key.as_str())
+                .map_err(redb::Error::from)?
+                .is_none()
+            {
+                return Err(Error::NotFound(term_key));
+            }
+            key_changes.push(KeyChange::Removed(term_key));
+        }
+    }
+    Ok(())
+}
+
+impl Get for EmbeddedTerms {
+    fn get(&self, term_key: &str) -> Result<Option<FatTerm>, Error> {
+        let read = self.db.begin_read().map_err(redb::Error::from)?;
+        let table = read.open_table(TERMS).map_err(redb::Error::from)?;
+        let Some(raw_term) = table.get(term_key).map_err(redb::Error::from)? else {
+            return Ok(None);
+        };
+        let (_, fat_term) = parse_fat_term(raw_term.value())?;
+        Ok(Some(fat_term))
+    }
+}
+
+impl Put for EmbeddedTerms {
+    fn put(&mut self, term_key: &str, term: FatTerm) -> Result<(), Error> {
+        let mut transaction = self.begin();
+        transaction.put(term_key, term);
+        self.commit(transaction)
+    }
+}
+
+impl Delete for EmbeddedTerms {
+    fn delete(&mut self, term_key: &str) -> Result<(), Error> {
+        let mut transaction = self.begin();
+        transaction.delete(term_key);
+        self.commit(transaction)
+    }
+}
+
+impl Transact for EmbeddedTerms {
+    fn commit(&mut self, transaction: Transaction) -> Result<(), Error> {
+        let write = self.db.begin_write().map_err(redb::Error::from)?;
+        let mut key_changes = vec![];
+        {
+            let mut table = write.open_table(TERMS).map_err(redb::Error::from)?;
+            for change in transaction.changes {
+                stage(&mut table, change, &mut key_changes)?;
+            }
+        }
+        write.commit().map_err(redb::Error::from)?;
+        self.apply(key_changes);
+        Ok(())
+    }
+}
+
+impl Keys for EmbeddedTerms {
+    fn keys(&self) -> &Vec<String> {
+        &self.keys
+    }
+}
+
+impl Load for EmbeddedTerms {
+    type Store = EmbeddedTerms;
+
+    fn load(path: &Path) -> Result<Self::Store, Error> {
+        EmbeddedTerms::new(path)
+    }
+}
+
+impl Consult for EmbeddedTerms {
+    fn consult(&mut self, term: &BoundTerm) -> Result<Vec<HashMap<String, String>>, Error> {
+        let knowledge = if self.engine_is_stale {
+            Some(self.encode()?)
+        } else {
+            None
+        };
+        let engine = self
+            .engine
+            .get_or_insert_with(|| MachineBuilder::default().build());
+        if let Some(knowledge) = knowledge {
+            engine.load_module_string("knowledge", knowledge);
+            self.engine_is_stale = false;
+        }
+        engine::consult(engine, term)
+    }
+}
+
+impl TermsStore for EmbeddedTerms {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge::store::test_utils::{knowledge_dir, term};
+
+    #[test]
+    fn test_import_change_export() {
+        let path = knowledge_dir("embedded");
+        fs::write(
+            path.join(PAGE_NAME),
+            term("second").encode() + &term("first").encode(),
+        )
+        .unwrap();
+
+        let mut terms = EmbeddedTerms::new(&path).unwrap();
+        assert_eq!(
+            terms.keys(),
+            &vec!["first/1".to_string(), "second/1".to_string()]
+        );
+        terms.put("third/1", term("third")).unwrap();
+        terms.put("first/1", term("renamed")).unwrap();
+        assert!(matches!(
+            terms.put("second/1", term("third")),
+            Err(Error::AlreadyPresent(_))
+        ));
+        terms.delete("third/1").unwrap();
+        drop(terms);
+
+        // page.pl is only read into a new database
+        let terms = EmbeddedTerms::new(&path).unwrap();
+        assert_eq!(
+            terms.keys(),
+            &vec!["renamed/1".to_string(), "second/1".to_string()]
+        );
+        assert_eq!(terms.get("renamed/1").unwrap(), Some(term("renamed")));
+        terms.export().unwrap();
+        assert_eq!(
+            fs::read_to_string(path.join(PAGE_NAME)).unwrap(),
+            term("renamed").encode() + &term("second").encode()
+        );
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_import_is_retried() {
+        let path = knowledge_dir("embedded_failed_import");
+        fs::write(
+            path.join(PAGE_NAME),
+            term("first").encode() + &term("first").encode(),
+        )
+        .unwrap();
+        assert!(matches!(
+            EmbeddedTerms::new(&path),
+            Err(Error::Duplicate(_))
+        ));
+        assert!(!path.join(DATABASE_NAME).exists());
+        assert!(!temp_path(&path.join(DATABASE_NAME)).exists());
+
+        fs::write(path.join(PAGE_NAME), term("first").encode()).unwrap();
+        let terms = EmbeddedTerms::new(&path).unwrap();
+        assert_eq!(terms.keys(), &vec!["first/1".to_string()]);
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
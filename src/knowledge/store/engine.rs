@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use scryer_prolog::{LeafAnswer, Machine};
+
+use crate::knowledge::model::term::{
+    arg::{encode_atom, Arg},
+    bound_term::BoundTerm,
+};
+use crate::knowledge::store::Error;
+
+// Running queries against a scryer machine that has the knowledge loaded - shared by the stores
+// that implement Consult
+
+pub(super) fn consult(
+    engine: &mut Machine,
+    term: &BoundTerm,
+) -> Result<Vec<HashMap<String, String>>, Error> {
+    // the term must be finished with a '.' to be a valid prolog query
+    let mut consult_results = vec![];
+    let results = engine.run_query(term.encode() + ".");
+    for binding in results {
+        match binding {
+            Ok(b) => match b {
+                LeafAnswer::True => {
+                    // success without any bindings (e.g. a zero-arity or fully anchored term)
+                    consult_results.push(HashMap::new());
+                }
+                LeafAnswer::False => {
+                    // TODO: represent failure
+                }
+                LeafAnswer::Exception(exception) => {
+                    return Err(Error::Engine(encode_engine_term(&exception)));
+                }
+                LeafAnswer::LeafAnswer {
+                    bindings: arg_binding,
+                    ..
+                } => {
+                    let mut bound = HashMap::with_capacity(arg_binding.len());
+                    for (var_name, value) in &arg_binding {
+                        bound.insert(var_name.to_owned(), encode_engine_term(value));
+                    }
+                    consult_results.push(bound);
+                }
+            },
+            Err(e) => return Err(Error::Engine(format!("{:?}", e))),
+        };
+    }
+    Ok(consult_results)
+}
+
+fn encode_engine_term(term: &scryer_prolog::Term) -> String {
+    match term {
+        scryer_prolog::Term::Atom(s) => encode_atom(s),
+        scryer_prolog::Term::Var(s) => s.to_owned(),
+        scryer_prolog::Term::String(s) => Arg::String(s.to_owned()).encode(),
+        scryer_prolog::Term::Integer(i) => i.to_string(),
+        scryer_prolog::Term::Float(f) => f.to_string(),
+        scryer_prolog::Term::List(items) => {
+            let items: Vec<String> = items.iter().map(encode_engine_term).collect();
+            format!("[{}]", items.join(","))
+        }
+        scryer_prolog::Term::Compound(name, args) => {
+            let args: Vec<String> = args.iter().map(encode_engine_term).collect();
+            format!("{}({})", encode_atom(name), args.join(","))
+        }
+        other => format!("{:?}", other),
+    }
+}
@@ -1,42 +1,97 @@
 use std::collections::HashMap;
 use std::path::Path;
+use std::{fs, io};
 
-use crate::knowledge::model::fat_term::FatTerm;
+use nom::Offset;
+use scryer_prolog::{Machine, MachineBuilder};
+
+use crate::knowledge::model::fat_term::{parse_fat_term, FatTerm};
 use crate::knowledge::model::term::bound_term::BoundTerm;
-use crate::knowledge::store::{Consult, Delete, Get, Keys, Load, Put, TermsStore};
+use crate::knowledge::store::{
+    engine, Consult, Delete, Error, Get, Keys, Load, Put, Staged, TermsStore, Transact,
+    Transaction, PAGE_NAME,
+};
 
+// A store that never writes anything - loading reads page.pl, but the changes stay in memory
 pub struct InMemoryTerms {
     map: HashMap<String, FatTerm>,
+    // the keys in the order of page.pl - new terms go at the end
     vec: Vec<String>,
+    // built on the first consult - most users of the store never consult
+    engine: Option<Machine>,
+    // set by every change - the engine is reloaded on the next consult
+    engine_is_stale: bool,
 }
 
 impl InMemoryTerms {
     pub fn new(map: HashMap<String, FatTerm>) -> Self {
         let vec = map.keys().cloned().collect();
-        Self { map, vec }
+        Self {
+            map,
+            vec,
+            engine: None,
+            engine_is_stale: true,
+        }
+    }
+
+    // what the engine gets - the terms encoded the way page.pl would have them
+    fn encode(&self) -> String {
+        self.vec.iter().map(|key| self.map[key].encode()).collect()
     }
 }
 
 impl Get for InMemoryTerms {
-    fn get(&self, term_name: &str) -> Option<FatTerm> {
-        self.map.get(term_name).cloned()
+    fn get(&self, term_key: &str) -> Result<Option<FatTerm>, Error> {
+        Ok(self.map.get(term_key).cloned())
     }
 }
 
 impl Put for InMemoryTerms {
-    fn put(&mut self, term_name: &str, term: FatTerm) {
-        if self.map.contains_key(term_name) {
-            self.map.remove(term_name);
+    fn put(&mut self, term_key: &str, term: FatTerm) -> Result<(), Error> {
+        let new_key = term.key();
+        if new_key != term_key && self.map.contains_key(&new_key) {
+            return Err(Error::AlreadyPresent(new_key));
+        }
+        if self.map.remove(term_key).is_some() {
+            let pos = self.vec.iter().position(|t| t == term_key).unwrap();
+            self.vec[pos] = new_key.clone();
+        } else {
+            self.vec.push(new_key.clone());
         }
-        self.map.insert(term.meta.term.name.clone(), term);
+        self.map.insert(new_key, term);
+        self.engine_is_stale = true;
+        Ok(())
     }
 }
 
 impl Delete for InMemoryTerms {
-    fn delete(&mut self, term_name: &str) {
-        self.map.remove(term_name);
-        let pos = self.vec.iter().position(|t| t == term_name).unwrap();
-        self.vec.swap_remove(pos);
+    fn delete(&mut self, term_key: &str) -> Result<(), Error> {
+        if self.map.remove(term_key).is_none() {
+            return Err(Error::NotFound(term_key.to_owned()));
+        }
+        self.vec.retain(|t| t != term_key);
+        self.engine_is_stale = true;
+        Ok(())
+    }
+}
+
+impl Transact for InMemoryTerms {
+    fn commit(&mut self, transaction: Transaction) -> Result<(), Error> {
+        let map = self.map.clone();
+        let vec = self.vec.clone();
+
+        let committed = transaction
+            .changes
+            .into_iter()
+            .try_for_each(|change| match change {
+                Staged::Put(term_key, term) => self.put(&term_key, term),
+                Staged::Delete(term_key) => self.delete(&term_key),
+            });
+        if committed.is_err() {
+            self.map = map;
+            self.vec = vec;
+        }
+        committed
     }
 }
 
@@ -47,17 +102,100 @@ impl Keys for InMemoryTerms {
 }
 
 impl Load for InMemoryTerms {
-    fn load(_path: &Path) -> InMemoryTerms {
-        todo!()
+    // a missing page.pl is an empty store, same as for the persistent one - unlike it, a term
+    // that doesn't parse fails the whole load. So does a term that is there twice
+    fn load(path: &Path) -> Result<InMemoryTerms, Error> {
+        let page = match fs::read_to_string(path.join(PAGE_NAME)) {
+            Ok(page) => page,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut terms = InMemoryTerms::new(HashMap::new());
+        let mut rest = page.as_str();
+        while !rest.trim().is_empty() {
+            let (leftover, fat_term) =
+                parse_fat_term(rest).map_err(|err| err.offset_by(&page[..page.offset(rest)]))?;
+            // refused, same as by the file-backed stores - keeping either copy would drop the other
+            let term_key = fat_term.key();
+            if terms.map.contains_key(&term_key) {
+                return Err(Error::Duplicate(term_key));
+            }
+            terms
+                .put(&term_key, fat_term)
+                .expect("a put under the term's own key doesn't collide");
+            rest = leftover;
+        }
+        Ok(terms)
     }
 
     type Store = InMemoryTerms;
 }
 
 impl Consult for InMemoryTerms {
-    fn consult(&mut self, _: &BoundTerm) -> Vec<HashMap<String, String>> {
-        todo!()
+    fn consult(&mut self, term: &BoundTerm) -> Result<Vec<HashMap<String, String>>, Error> {
+        let knowledge = self.engine_is_stale.then(|| self.encode());
+        let engine = self
+            .engine
+            .get_or_insert_with(|| MachineBuilder::default().build());
+        if let Some(knowledge) = knowledge {
+            engine.load_module_string("knowledge", knowledge);
+            self.engine_is_stale = false;
+        }
+        engine::consult(engine, term)
     }
 }
 
 impl TermsStore for InMemoryTerms {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge::store::test_utils::{knowledge_dir, term};
+
+    #[test]
+    fn test_load_and_change() {
+        let path = knowledge_dir("in_memory");
+        fs::write(
+            path.join(PAGE_NAME),
+            term("first").encode() + &term("second").encode(),
+        )
+        .unwrap();
+
+        let mut terms = InMemoryTerms::load(&path).unwrap();
+        fs::remove_dir_all(&path).unwrap();
+        assert_eq!(
+            terms.keys(),
+            &vec!["first/1".to_string(), "second/1".to_string()]
+        );
+
+        terms.put("third/1", term("third")).unwrap();
+        terms.put("first/1", term("renamed")).unwrap();
+        terms.delete("third/1").unwrap();
+        assert_eq!(
+            terms.keys(),
+            &vec!["renamed/1".to_string(), "second/1".to_string()]
+        );
+        assert_eq!(
+            terms.encode(),
+            term("renamed").encode() + &term("second").encode()
+        );
+        assert!(matches!(terms.delete("third/1"), Err(Error::NotFound(_))));
+    }
+
+    #[test]
+    fn test_duplicate_is_refused() {
+        let path = knowledge_dir("in_memory_duplicate");
+        fs::write(
+            path.join(PAGE_NAME),
+            term("first").encode() + &term("second").encode() + &term("first").encode(),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            InMemoryTerms::load(&path),
+            Err(Error::Duplicate(key)) if key == "first/1"
+        ));
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
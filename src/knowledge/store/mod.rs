@@ -1,28 +1,63 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, io, path::Path};
 
-use bincode::Encode;
-use bincode_derive::Decode;
+use thiserror::Error;
 
 use crate::knowledge::model::fat_term::FatTerm;
+use crate::knowledge::model::parsing::ParseError;
 use crate::knowledge::model::term::bound_term::BoundTerm;
 
+mod descriptor;
+pub mod directory;
+pub mod embedded;
+mod engine;
 pub mod in_memory;
+pub mod module;
+pub mod past;
 pub mod persistent;
+#[cfg(test)]
+pub(crate) mod test_utils;
 
-#[derive(Debug)]
+#[derive(Error, Debug)]
 pub enum Error {
-    NotFound,
-    AlreadyPresent,
-    //TODO:  InvalidTerm,
+    #[error("{0} doesn't exist")]
+    NotFound(String),
+    // the put would leave two terms under the same key
+    #[error("{0} already exists")]
+    AlreadyPresent(String),
+    // a term the knowledge has twice - a store can't tell the two apart, so it's left to be fixed
+    // by hand
+    #[error("{0} is in the knowledge more than once")]
+    Duplicate(String),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error("can't write the knowledge: {0}")]
+    Io(#[from] io::Error),
+    #[error("the engine failed: {0}")]
+    Engine(String),
+    // written by a newer build - rebuilding it would drop whatever the newer format knows
+    #[error("the descriptor is version {0}, but only up to version {1} can be read")]
+    DescriptorVersion(u32, u32),
+    // boxed - it's several times bigger than the rest
+    #[error("the database failed: {0}")]
+    Database(Box<redb::Error>),
 }
 
+impl From<redb::Error> for Error {
+    fn from(err: redb::Error) -> Self {
+        Error::Database(Box::new(err))
+    }
+}
+
+// terms are stored under their "name/arity" key - see FatTerm::key
 pub trait Get {
-    fn get(&self, term_name: &str) -> Option<FatTerm>;
+    // Ok(None) when there is no such term, Err when it is there but can't be read
+    fn get(&self, term_key: &str) -> Result<Option<FatTerm>, Error>;
 }
 
 pub trait Put {
-    // the term.meta.term.name takes precedence to the provided term_name
-    fn put(&mut self, term_name: &str, term: FatTerm);
+    // the key of the term takes precedence to the provided term_key - it's an error when another
+    // term is already stored under it
+    fn put(&mut self, term_key: &str, term: FatTerm) -> Result<(), Error>;
 }
 
 pub trait Keys {
@@ -30,13 +65,13 @@ pub trait Keys {
 }
 
 pub trait Delete {
-    fn delete(&mut self, term_name: &str);
+    fn delete(&mut self, term_key: &str) -> Result<(), Error>;
 }
 
 pub trait Load {
     type Store: Get + Put + Keys + Delete;
 
-    fn load(path: &Path) -> Self::Store;
+    fn load(path: &Path) -> Result<Self::Store, Error>;
 }
 
 pub enum ConsultResult {
@@ -46,20 +81,60 @@ pub enum ConsultResult {
 }
 
 pub trait Consult {
-    fn consult(&mut self, term: &BoundTerm) -> Vec<HashMap<String, String>>;
+    fn consult(&mut self, term: &BoundTerm) -> Result<Vec<HashMap<String, String>>, Error>;
 }
 
-pub trait TermsStore: Get + Put + Keys + Delete + Consult {}
+// Puts and deletes staged to be committed together - a transaction that is dropped without a
+// commit changes nothing
+#[derive(Default)]
+pub struct Transaction {
+    changes: Vec<Staged>,
+}
 
-const PAGE_NAME: &str = "page.pl";
-const DESCRIPTOR_NAME: &str = "descriptor";
+enum Staged {
+    Put(String, FatTerm),
+    Delete(String),
+}
+
+impl Transaction {
+    // same as Put::put, once committed
+    pub fn put(&mut self, term_key: &str, term: FatTerm) {
+        self.changes.push(Staged::Put(term_key.to_owned(), term));
+    }
+
+    // same as Delete::delete, once committed
+    pub fn delete(&mut self, term_key: &str) {
+        self.changes.push(Staged::Delete(term_key.to_owned()));
+    }
 
-#[derive(Decode, Encode, Clone)]
-struct DescriptorEntry {
-    name: String,
-    offset: usize,
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
 
-    len: usize,
-    // this field should be skipped during encoding/decoding - couldn't find a way to do that with bincode
-    is_deleted: bool,
+    // nothing staged was applied - this only makes the intent explicit
+    pub fn rollback(self) {}
+}
+
+pub trait Transact {
+    fn begin(&self) -> Transaction {
+        Transaction::default()
+    }
+
+    // the staged changes are applied in order, as if by put and delete, but are written once -
+    // either all of them make it or, on an error, the store is left as it was
+    fn commit(&mut self, transaction: Transaction) -> Result<(), Error>;
+}
+
+pub trait TermsStore: Get + Put + Keys + Delete + Consult + Transact {}
+
+const PAGE_NAME: &str = "page.pl";
+const DESCRIPTOR_NAME: &str = "descriptor";
+const DATABASE_NAME: &str = "knowledge.redb";
+
+// a file a page.pl or a one-file-per-term module keeps its knowledge in - temp files and the
+// database of an embedded module are not
+pub(crate) fn is_knowledge_file(file_name: &str) -> bool {
+    file_name == DESCRIPTOR_NAME
+        || file_name == directory::LAYOUT_MARKER
+        || file_name.ends_with(".pl")
 }
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::knowledge::model::fat_term::FatTerm;
+use crate::knowledge::model::term::bound_term::BoundTerm;
+use crate::knowledge::store::{
+    directory::DirectoryTerms, persistent::TermsWithEngine, Consult, Delete, Error, Get, Keys,
+    Load, Put, TermsStore, Transact, Transaction,
+};
+
+// The store of a module as the module itself asks for it - one file per term when it has the
+// layout marker of DirectoryTerms, page.pl otherwise
+pub enum ModuleTerms {
+    Page(TermsWithEngine),
+    Directory(DirectoryTerms),
+}
+
+impl Get for ModuleTerms {
+    fn get(&self, term_key: &str) -> Result<Option<FatTerm>, Error> {
+        match self {
+            ModuleTerms::Page(terms) => terms.get(term_key),
+            ModuleTerms::Directory(terms) => terms.get(term_key),
+        }
+    }
+}
+
+impl Put for ModuleTerms {
+    fn put(&mut self, term_key: &str, term: FatTerm) -> Result<(), Error> {
+        match self {
+            ModuleTerms::Page(terms) => terms.put(term_key, term),
+            ModuleTerms::Directory(terms) => terms.put(term_key, term),
+        }
+    }
+}
+
+impl Delete for ModuleTerms {
+    fn delete(&mut self, term_key: &str) -> Result<(), Error> {
+        match self {
+            ModuleTerms::Page(terms) => terms.delete(term_key),
+            ModuleTerms::Directory(terms) => terms.delete(term_key),
+        }
+    }
+}
+
+impl Transact for ModuleTerms {
+    fn commit(&mut self, transaction: Transaction) -> Result<(), Error> {
+        match self {
+            ModuleTerms::Page(terms) => terms.commit(transaction),
+            ModuleTerms::Directory(terms) => terms.commit(transaction),
+        }
+    }
+}
+
+impl Keys for ModuleTerms {
+    fn keys(&self) -> &Vec<String> {
+        match self {
+            ModuleTerms::Page(terms) => terms.keys(),
+            ModuleTerms::Directory(terms) => terms.keys(),
+        }
+    }
+}
+
+impl Consult for ModuleTerms {
+    fn consult(&mut self, term: &BoundTerm) -> Result<Vec<HashMap<String, String>>, Error> {
+        match self {
+            ModuleTerms::Page(terms) => terms.consult(term),
+            ModuleTerms::Directory(terms) => terms.consult(term),
+        }
+    }
+}
+
+impl Load for ModuleTerms {
+    type Store = ModuleTerms;
+
+    fn load(path: &Path) -> Result<Self::Store, Error> {
+        if DirectoryTerms::is_used_by(path) {
+            return Ok(ModuleTerms::Directory(DirectoryTerms::load(path)?));
+        }
+        Ok(ModuleTerms::Page(TermsWithEngine::load(path)?))
+    }
+}
+
+impl TermsStore for ModuleTerms {}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use scryer_prolog::{Machine, MachineBuilder};
+
+use crate::knowledge::model::fat_term::{parse_fat_term, FatTerm};
+use crate::knowledge::model::term::bound_term::BoundTerm;
+use crate::knowledge::store::{engine, persistent::rebuild_descriptor, Consult, Error, Get, Keys};
+
+// The knowledge as it was at a past revision of the module - read from the files committed then
+// and never written to. There is no Put, Delete or Transact, so it can't end up in the UI in place
+// of the module's store
+
+pub struct PastTerms {
+    // "name/arity" -> the term as it was committed
+    raw_terms: HashMap<String, String>,
+    // sorted
+    keys: Vec<String>,
+    // built on the first consult - nothing changes it after that
+    engine: Option<Machine>,
+}
+
+impl PastTerms {
+    // the contents of the committed knowledge files - page.pl or the files of a
+    // one-file-per-term module. A term in more than one of them is taken from the first
+    pub(crate) fn from_pages<'a>(pages: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut raw_terms = HashMap::new();
+        for page in pages {
+            for entry in rebuild_descriptor(page) {
+                raw_terms
+                    .entry(entry.name)
+                    .or_insert_with(|| page[entry.offset..entry.offset + entry.len].to_owned());
+            }
+        }
+        let mut keys: Vec<String> = raw_terms.keys().cloned().collect();
+        keys.sort();
+        Self {
+            raw_terms,
+            keys,
+            engine: None,
+        }
+    }
+
+    pub(crate) fn raw_term(&self, term_key: &str) -> Option<&str> {
+        self.raw_terms.get(term_key).map(String::as_str)
+    }
+}
+
+impl Get for PastTerms {
+    fn get(&self, term_key: &str) -> Result<Option<FatTerm>, Error> {
+        let Some(raw_term) = self.raw_term(term_key) else {
+            return Ok(None);
+        };
+        let (_, fat_term) = parse_fat_term(raw_term)?;
+        Ok(Some(fat_term))
+    }
+}
+
+impl Keys for PastTerms {
+    fn keys(&self) -> &Vec<String> {
+        &self.keys
+    }
+}
+
+impl Consult for PastTerms {
+    fn consult(&mut self, term: &BoundTerm) -> Result<Vec<HashMap<String, String>>, Error> {
+        let engine = match &mut self.engine {
+            Some(engine) => engine,
+            None => {
+                let mut engine = MachineBuilder::default().build();
+                let knowledge: String = self
+                    .keys
+                    .iter()
+                    .map(|key| self.raw_terms[key].as_str())
+                    .collect();
+                engine.load_module_string("knowledge", knowledge);
+                self.engine.insert(engine)
+            }
+        };
+        engine::consult(engine, term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge::store::test_utils::raw_term;
+
+    #[test]
+    fn test_from_pages() {
+        let first = raw_term("first", "a") + &raw_term("second", "a");
+        let second = raw_term("first", "b") + &raw_term("third", "a");
+        let terms = PastTerms::from_pages([first.as_str(), second.as_str()]);
+
+        assert_eq!(
+            terms.keys(),
+            &vec![
+                "first/1".to_string(),
+                "second/1".to_string(),
+                "third/1".to_string()
+            ]
+        );
+        assert_eq!(
+            terms.get("first/1").unwrap(),
+            Some(parse_fat_term(&raw_term("first", "a")).unwrap().1)
+        );
+        assert_eq!(terms.get("fourth/1").unwrap(), None);
+    }
+}
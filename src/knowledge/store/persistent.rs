@@ -1,18 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::fs::{File, OpenOptions};
-use std::io::{BufReader, BufWriter};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use std::{fs, io};
 
+use crate::knowledge::model::comment::name_description::parse_term_name;
 use crate::knowledge::model::fat_term::{parse_fat_term, FatTerm};
-use crate::knowledge::model::term::bound_term::BoundTerm;
+use crate::knowledge::model::parsing::{clause_layout, term_header_start};
+use crate::knowledge::model::term::{bound_term::BoundTerm, split_term_key, term_key};
 use crate::knowledge::store::{
-    Consult, Delete, DescriptorEntry, Get, Keys, Load, Put, TermsStore, DESCRIPTOR_NAME, PAGE_NAME,
+    descriptor::{self, DescriptorEntry},
+    engine, Consult, Delete, Error, Get, Keys, Load, Put, Staged, TermsStore, Transact,
+    Transaction, DESCRIPTOR_NAME, PAGE_NAME,
 };
-use bincode::{config, decode_from_std_read, encode_into_std_write};
-use scryer_prolog::Machine;
-use scryer_prolog::Term::Atom;
-use scryer_prolog::{LeafAnswer, MachineBuilder};
+use scryer_prolog::{Machine, MachineBuilder};
 
 pub struct TermsWithEngine {
     terms: Terms,
@@ -20,16 +21,27 @@ pub struct TermsWithEngine {
 }
 
 impl Get for TermsWithEngine {
-    fn get(&self, term_name: &str) -> Option<FatTerm> {
-        self.terms.get(term_name)
+    fn get(&self, term_key: &str) -> Result<Option<FatTerm>, Error> {
+        self.terms.get(term_key)
     }
 }
 
 impl Put for TermsWithEngine {
-    fn put(&mut self, term_name: &str, term: FatTerm) {
-        self.terms.put(term_name, term);
+    fn put(&mut self, term_key: &str, term: FatTerm) -> Result<(), Error> {
+        self.terms.put(term_key, term)?;
         self.engine
             .load_module_string("knowledge", self.terms.buffer.clone());
+        Ok(())
+    }
+}
+
+impl Transact for TermsWithEngine {
+    // the engine is reloaded once for the whole transaction
+    fn commit(&mut self, transaction: Transaction) -> Result<(), Error> {
+        self.terms.commit(transaction)?;
+        self.engine
+            .load_module_string("knowledge", self.terms.buffer.clone());
+        Ok(())
     }
 }
 
@@ -40,51 +52,19 @@ impl Keys for TermsWithEngine {
 }
 
 impl Delete for TermsWithEngine {
-    fn delete(&mut self, term_name: &str) {
-        self.terms.delete(term_name);
+    fn delete(&mut self, term_key: &str) -> Result<(), Error> {
+        self.terms.delete(term_key)?;
         // TODO: check if it's too slow to load all of the buffer every time a term is deleted
         // TODO: maybe expose `.flush` that guarantees that the buffer has been loaded in the engine
         self.engine
-            .load_module_string("knowledge", self.terms.buffer.clone())
+            .load_module_string("knowledge", self.terms.buffer.clone());
+        Ok(())
     }
 }
 
 impl Consult for TermsWithEngine {
-    fn consult(&mut self, term: &BoundTerm) -> Vec<HashMap<String, String>> {
-        // the term must be finished with a '.' to be a valid prolog query
-        let mut consult_results = vec![];
-        let results = self.engine.run_query(term.encode() + ".");
-        for binding in results {
-            match binding {
-                Ok(b) => match b {
-                    LeafAnswer::True => {
-                        // TODO: represent success
-                    }
-                    LeafAnswer::False => {
-                        // TODO: represent failure
-                    }
-                    LeafAnswer::Exception(_) => {}
-                    LeafAnswer::LeafAnswer {
-                        bindings: arg_binding,
-                        ..
-                    } => {
-                        let mut bound = HashMap::with_capacity(arg_binding.len());
-                        for binding in &arg_binding {
-                            if let Atom(s) = binding.1 {
-                                bound.insert(binding.0.to_owned(), s.to_owned());
-                            } else {
-                                panic!("all values are currently expected to be strings");
-                            }
-                        }
-                        consult_results.push(bound);
-                    }
-                },
-                Err(e) => {
-                    panic!("{:?}", e)
-                }
-            };
-        }
-        consult_results
+    fn consult(&mut self, term: &BoundTerm) -> Result<Vec<HashMap<String, String>>, Error> {
+        engine::consult(&mut self.engine, term)
     }
 }
 
@@ -93,13 +73,13 @@ impl TermsStore for TermsWithEngine {}
 impl Load for TermsWithEngine {
     type Store = TermsWithEngine;
 
-    fn load(path: &Path) -> Self::Store {
-        let terms = Terms::load(path);
+    fn load(path: &Path) -> Result<Self::Store, Error> {
+        let terms = Terms::load(path)?;
         let builder = MachineBuilder::default();
         let mut engine = builder.build();
         engine.load_module_string("knowledge", terms.buffer.clone());
 
-        TermsWithEngine { terms, engine }
+        Ok(TermsWithEngine { terms, engine })
     }
 }
 
@@ -111,54 +91,83 @@ pub struct Terms {
     keys: Vec<String>,
     base_path: PathBuf,
     buffer: String,
+    // set when the last write-through failed - the next change or the drop try again
+    unpersisted: bool,
 }
 
+// a change is first written next to the real files and then renamed over them - renaming page.pl
+// is the point where the change is committed, see recover()
+pub(super) const TEMP_SUFFIX: &str = ".tmp";
+
 impl Drop for Terms {
     fn drop(&mut self) {
-        self.persist()
+        if self.unpersisted {
+            // nothing to do about a failure this late
+            let _ = self.persist();
+        }
     }
 }
 
 impl Terms {
-    fn persist(&self) {
-        let descriptor = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(self.base_path.join(DESCRIPTOR_NAME))
-            .unwrap();
-        let mut buf_writer = BufWriter::new(descriptor);
+    // every change is written through right away, so that neither a panic nor a kill lose
+    // anything that was already put
+    fn write_through(&mut self) -> io::Result<()> {
+        self.compact();
+        let persisted = self.persist();
+        self.unpersisted = persisted.is_err();
+        persisted
+    }
 
-        encode_into_std_write(&self.descriptor, &mut buf_writer, config::standard()).unwrap();
+    // drops the entries of deleted terms - they only keep the positions of the rest in the index
+    // valid until then, so the index is derived anew
+    pub fn compact(&mut self) {
+        if !self.descriptor.iter().any(|entry| entry.is_deleted) {
+            return;
+        }
+        self.descriptor.retain(|entry| !entry.is_deleted);
+        self.index = index_of(&self.descriptor);
+    }
 
-        fs::write(self.base_path.join(PAGE_NAME), &self.buffer).unwrap();
+    fn persist(&self) -> io::Result<()> {
+        let page_path = self.base_path.join(PAGE_NAME);
+        let descriptor_path = self.base_path.join(DESCRIPTOR_NAME);
+        let page_temp_path = temp_path(&page_path);
+        let descriptor_temp_path = temp_path(&descriptor_path);
+
+        write_synced(&page_temp_path, |file| {
+            file.write_all(self.buffer.as_bytes())
+        })?;
+        write_synced(&descriptor_temp_path, |file| {
+            file.write_all(&descriptor::encode(&self.descriptor, &self.buffer))
+        })?;
+
+        fs::rename(&page_temp_path, &page_path)?;
+        sync_dir(&self.base_path)?;
+        fs::rename(&descriptor_temp_path, &descriptor_path)?;
+        sync_dir(&self.base_path)
     }
 
-    pub fn new(path: &Path) -> Self {
+    // finishes or drops a write that was interrupted - a temp page means that page.pl was not
+    // replaced yet and the old files are still a consistent pair. A temp descriptor on its own
+    // means that the new page.pl is in place and only the descriptor is left to be renamed
+    fn recover(path: &Path) -> io::Result<()> {
+        let page_temp_path = temp_path(&path.join(PAGE_NAME));
         let descriptor_path = path.join(DESCRIPTOR_NAME);
+        let descriptor_temp_path = temp_path(&descriptor_path);
 
-        let mut descriptor_vec = if !descriptor_path.exists() {
-            File::create(&descriptor_path).unwrap();
-            vec![]
-        } else {
-            let descriptor = OpenOptions::new().read(true).open(descriptor_path).unwrap();
-
-            let mut descriptor = BufReader::new(descriptor);
-            let descriptor_vec: Vec<DescriptorEntry> =
-                decode_from_std_read(&mut descriptor, config::standard()).unwrap();
-            descriptor_vec
-        };
-
-        descriptor_vec.retain(|x| !x.is_deleted);
-
-        let mut index = HashMap::new();
-        for (entry_idx, entry) in descriptor_vec.iter().enumerate() {
-            index.insert(entry.name.clone(), entry_idx);
+        if page_temp_path.exists() {
+            fs::remove_file(&page_temp_path)?;
+            if descriptor_temp_path.exists() {
+                fs::remove_file(&descriptor_temp_path)?;
+            }
+        } else if descriptor_temp_path.exists() {
+            fs::rename(&descriptor_temp_path, &descriptor_path)?;
         }
-        let mut keys = Vec::with_capacity(descriptor_vec.len());
+        sync_dir(path)
+    }
 
-        for entry in &descriptor_vec {
-            keys.push(entry.name.clone());
-        }
+    pub fn new(path: &Path) -> Result<Self, Error> {
+        Self::recover(path)?;
 
         let page_path = path.join(PAGE_NAME);
         let page_content = OpenOptions::new()
@@ -166,20 +175,119 @@ impl Terms {
             .truncate(false)
             .read(true)
             .write(true)
-            .open(page_path)
-            .unwrap();
-        let page_content = io::read_to_string(page_content).unwrap();
+            .open(page_path)?;
+        let page_content = io::read_to_string(page_content)?;
+
+        // page.pl is the source of truth - a descriptor that is missing, can't be decoded or
+        // doesn't match page.pl (hand edits, a checkout without it) is rebuilt from it
+        let descriptor_path = path.join(DESCRIPTOR_NAME);
+        let decoded = if descriptor_path.exists() {
+            Some(descriptor::decode(&fs::read(descriptor_path)?))
+        } else {
+            None
+        };
+        // anything but the current version is written anew right away
+        let (mut descriptor_vec, needs_write) = match decoded {
+            Some(Ok(descriptor))
+                if descriptor.page_checksum == Some(descriptor::checksum(&page_content))
+                    || matches_page(&descriptor.entries, &page_content) =>
+            {
+                let is_current = descriptor.version == descriptor::VERSION;
+                (descriptor.entries, !is_current)
+            }
+            Some(Err(descriptor::DecodeError::UnsupportedVersion(version))) => {
+                return Err(Error::DescriptorVersion(version, descriptor::VERSION));
+            }
+            _ => (rebuild_descriptor(&page_content), true),
+        };
+
+        // descriptors written before terms were told apart by arity only have the name
+        for entry in descriptor_vec.iter_mut() {
+            if split_term_key(&entry.name).is_none() {
+                let raw_term = page_content.get(entry.offset..entry.offset + entry.len);
+                if let Some(Ok((_, fat_term))) = raw_term.map(parse_fat_term) {
+                    entry.name = fat_term.key();
+                }
+            }
+        }
+        check_unique(&descriptor_vec)?;
 
-        Self {
-            index,
+        let keys = descriptor_vec
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect();
+
+        let mut terms = Self {
+            index: index_of(&descriptor_vec),
             descriptor: descriptor_vec,
             base_path: path.to_owned(),
             buffer: page_content,
             keys,
+            unpersisted: false,
+        };
+        if needs_write {
+            terms.write_through()?;
         }
+        Ok(terms)
     }
 
-    fn edit(&mut self, term_name: &str, term_idx: usize, updated: &FatTerm) {
+    // a put without the write - nothing is changed when it fails
+    fn stage_put(&mut self, term_key: &str, term: FatTerm) -> Result<(), Error> {
+        let new_key = term.key();
+        if new_key != term_key && self.index.contains_key(&new_key) {
+            return Err(Error::AlreadyPresent(new_key));
+        }
+        match self.index.get(term_key) {
+            Some(&term_idx) => self.edit(term_key, term_idx, &term),
+            None => {
+                self.create(&new_key, term);
+                Ok(())
+            }
+        }
+    }
+
+    // a delete without the write - the descriptor entry is not dropped, rather it is marked as
+    // deleted until the next compact()
+    fn stage_delete(&mut self, term_key: &str) -> Result<(), Error> {
+        let deleted_entry_idx = *self
+            .index
+            .get(term_key)
+            .ok_or_else(|| Error::NotFound(term_key.to_owned()))?;
+        let deleted_entry = self.descriptor[deleted_entry_idx].to_owned();
+
+        if let Some(deleted_entry) = self.descriptor.get_mut(deleted_entry_idx) {
+            *deleted_entry = DescriptorEntry {
+                name: "".to_string(),
+                offset: 0,
+                len: 0,
+                is_deleted: true,
+            }
+        }
+
+        self.buffer.replace_range(
+            deleted_entry.offset..deleted_entry.offset + deleted_entry.len,
+            "",
+        );
+
+        // the entries deleted earlier in a transaction cover nothing - they have no offset to shift
+        for descriptor_entry in self.descriptor[deleted_entry_idx + 1..]
+            .iter_mut()
+            .filter(|entry| !entry.is_deleted)
+        {
+            descriptor_entry.offset -= deleted_entry.len;
+        }
+        self.index.remove(term_key);
+
+        self.keys.retain(|key| key != term_key);
+        Ok(())
+    }
+
+    fn edit(&mut self, term_key: &str, term_idx: usize, updated: &FatTerm) -> Result<(), Error> {
+        let keys_idx = self
+            .keys
+            .iter()
+            .position(|key| key == term_key)
+            .ok_or_else(|| Error::NotFound(term_key.to_owned()))?;
         let entry = &mut self.descriptor[term_idx];
         let original_len = entry.len;
         let updated_encoded = &updated.encode();
@@ -191,65 +299,66 @@ impl Terms {
 
         entry.len = updated_encoded.len();
 
-        self.descriptor[term_idx].name = updated.meta.term.name.to_owned();
-        for desriptor_entry in self.descriptor[term_idx + 1..].iter_mut() {
+        let updated_key = updated.key();
+        self.descriptor[term_idx].name = updated_key.clone();
+        for desriptor_entry in self.descriptor[term_idx + 1..]
+            .iter_mut()
+            .filter(|entry| !entry.is_deleted)
+        {
             let mut adjusted_offset = desriptor_entry.offset as i64;
             adjusted_offset += len_diff;
 
             desriptor_entry.offset = adjusted_offset as usize;
         }
-        self.index.remove(term_name);
-        self.index
-            .insert(updated.meta.term.name.to_string(), term_idx);
-        let keys_idx = self.keys.iter().position(|name| name == term_name).unwrap();
-        self.keys[keys_idx] = updated.meta.term.name.to_string();
+        self.index.remove(term_key);
+        self.index.insert(updated_key.clone(), term_idx);
+        self.keys[keys_idx] = updated_key;
+        Ok(())
     }
 
-    fn create(&mut self, term_name: &str, term: FatTerm) {
+    fn create(&mut self, term_key: &str, term: FatTerm) {
         let encoded_term = term.encode();
 
-        let mut new_entry_offset = 0;
+        // the last entry may be one deleted earlier in a transaction, so the end is the buffer's
+        let new_entry_offset = self.buffer.len();
         let new_entry_len = encoded_term.len();
-        if let Some(entry) = self.descriptor.last() {
-            new_entry_offset = entry.offset + entry.len;
-        }
 
         self.index
-            .insert(term_name.to_string(), self.descriptor.len());
+            .insert(term_key.to_string(), self.descriptor.len());
         self.descriptor.push(DescriptorEntry {
-            name: term_name.to_string(),
+            name: term_key.to_string(),
             offset: new_entry_offset,
             len: new_entry_len,
             is_deleted: false,
         });
 
-        self.keys.push(term_name.to_string());
+        self.keys.push(term_key.to_string());
 
         self.buffer.push_str(&encoded_term);
     }
 }
 
 impl Get for Terms {
-    fn get(&self, term_name: &str) -> Option<FatTerm> {
-        match self.index.get(term_name) {
+    fn get(&self, term_key: &str) -> Result<Option<FatTerm>, Error> {
+        match self.index.get(term_key) {
             Some(offset) => {
                 let entry = &self.descriptor[*offset];
                 let raw_term = &self.buffer[entry.offset..entry.offset + entry.len];
 
-                let (_, fat_term) = parse_fat_term(raw_term).unwrap();
-                Some(fat_term)
+                // errors are reported relative to the whole page.pl rather than to the term
+                let (_, fat_term) = parse_fat_term(raw_term)
+                    .map_err(|err| err.offset_by(&self.buffer[..entry.offset]))?;
+                Ok(Some(fat_term))
             }
-            None => None,
+            None => Ok(None),
         }
     }
 }
 
 impl Put for Terms {
-    fn put(&mut self, term_name: &str, term: FatTerm) {
-        match self.index.get(term_name) {
-            Some(&term_idx) => self.edit(term_name, term_idx, &term),
-            None => self.create(&term.meta.term.name.clone(), term),
-        }
+    fn put(&mut self, term_key: &str, term: FatTerm) -> Result<(), Error> {
+        self.stage_put(term_key, term)?;
+        Ok(self.write_through()?)
     }
 }
 
@@ -260,39 +369,422 @@ impl Keys for Terms {
 }
 
 impl Delete for Terms {
-    // delete doesn't delete the descriptor entry for the record - rather it just sets its len to 0
-    fn delete(&mut self, term_name: &str) {
-        let deleted_entry_idx = self.index.get(term_name).unwrap().to_owned();
-        let deleted_entry = self.descriptor[deleted_entry_idx].to_owned();
+    fn delete(&mut self, term_key: &str) -> Result<(), Error> {
+        self.stage_delete(term_key)?;
+        Ok(self.write_through()?)
+    }
+}
 
-        if let Some(deleted_entry) = self.descriptor.get_mut(deleted_entry_idx) {
-            *deleted_entry = DescriptorEntry {
-                name: "".to_string(),
-                offset: 0,
-                len: 0,
-                is_deleted: true,
+impl Transact for Terms {
+    fn commit(&mut self, transaction: Transaction) -> Result<(), Error> {
+        let index = self.index.clone();
+        let descriptor = self.descriptor.clone();
+        let keys = self.keys.clone();
+        let buffer = self.buffer.clone();
+
+        let committed = transaction
+            .changes
+            .into_iter()
+            .try_for_each(|change| match change {
+                Staged::Put(term_key, term) => self.stage_put(&term_key, term),
+                Staged::Delete(term_key) => self.stage_delete(&term_key),
+            })
+            .and_then(|_| Ok(self.write_through()?));
+        if committed.is_err() {
+            // a failed write leaves `unpersisted` set, so what's on disk is brought back to the
+            // restored state by the next change or the drop
+            self.index = index;
+            self.descriptor = descriptor;
+            self.keys = keys;
+            self.buffer = buffer;
+        }
+        committed
+    }
+}
+
+impl Load for Terms {
+    type Store = Terms;
+
+    fn load(path: &Path) -> Result<Self::Store, Error> {
+        Terms::new(path)
+    }
+}
+
+fn index_of(descriptor: &[DescriptorEntry]) -> HashMap<String, usize> {
+    descriptor
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| !entry.is_deleted)
+        .map(|(entry_idx, entry)| (entry.name.clone(), entry_idx))
+        .collect()
+}
+
+// the entries have to cover page.pl back to back and each has to start with the header of the
+// term it is named after
+fn matches_page(descriptor: &[DescriptorEntry], page: &str) -> bool {
+    let mut expected_offset = 0;
+    for entry in descriptor {
+        if entry.offset != expected_offset {
+            return false;
+        }
+        let Some(raw_term) = page.get(entry.offset..entry.offset + entry.len) else {
+            return false;
+        };
+        // descriptors from before terms were told apart by arity only have the name
+        let name = split_term_key(&entry.name)
+            .map(|(name, _)| name)
+            .unwrap_or(&entry.name);
+        if header_name(raw_term).as_deref() != Some(name) {
+            return false;
+        }
+        expected_offset += entry.len;
+    }
+    page[expected_offset..].trim().is_empty()
+}
+
+// splits page.pl into terms by parsing it. A term that doesn't parse runs up to the next term
+// header and is kept under the name from its own header and the number of its "@arg" lines, so
+// that it can still be opened and fixed. A term that is there twice gets two entries with the
+// same name - see check_unique
+pub(super) fn rebuild_descriptor(page: &str) -> Vec<DescriptorEntry> {
+    let mut descriptor: Vec<DescriptorEntry> = vec![];
+    let mut offset = 0;
+    while !page[offset..].trim().is_empty() {
+        let rest = &page[offset..];
+        let (len, term_key) = match parse_fat_term(rest) {
+            Ok((leftover, fat_term)) => (rest.len() - leftover.len(), fat_term.key()),
+            Err(_) => {
+                let len = next_header_offset(rest);
+                let raw_term = &rest[..len];
+                let arity = raw_term
+                    .lines()
+                    .filter(|line| line.trim_start_matches(['%', ' ']).starts_with("@arg"))
+                    .count();
+                (
+                    len,
+                    term_key(&header_name(raw_term).unwrap_or_default(), arity),
+                )
             }
+        };
+        descriptor.push(DescriptorEntry {
+            name: term_key,
+            offset,
+            len,
+            is_deleted: false,
+        });
+        offset += len;
+    }
+    descriptor
+}
+
+pub(super) fn check_unique(descriptor: &[DescriptorEntry]) -> Result<(), Error> {
+    let mut seen = HashSet::new();
+    match descriptor
+        .iter()
+        .filter(|entry| !entry.is_deleted)
+        .find(|entry| !seen.insert(entry.name.as_str()))
+    {
+        Some(duplicate) => Err(Error::Duplicate(duplicate.name.clone())),
+        None => Ok(()),
+    }
+}
+
+fn header_name(raw_term: &str) -> Option<String> {
+    let (rest, _) = clause_layout(raw_term).ok()?;
+    let (rest, _) = term_header_start(rest).ok()?;
+    let (_, name) = parse_term_name(rest).ok()?;
+    Some(name)
+}
+
+// the offset of the first line after the first one that starts a term header - or the end
+fn next_header_offset(i: &str) -> usize {
+    i.match_indices('\n')
+        .map(|(idx, _)| idx + 1)
+        .find(|&line_start| term_header_start(&i[line_start..]).is_ok())
+        .unwrap_or(i.len())
+}
+
+pub(super) fn temp_path(path: &Path) -> PathBuf {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(TEMP_SUFFIX);
+    PathBuf::from(temp_path)
+}
+
+pub(super) fn write_synced(
+    path: &Path,
+    write: impl FnOnce(&mut File) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    write(&mut file)?;
+    file.sync_all()
+}
+
+// makes the renames durable - directories can't be opened for syncing on every platform
+pub(super) fn sync_dir(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    File::open(path)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knowledge::store::test_utils::{knowledge_dir, term};
+
+    #[test]
+    fn test_changes_survive_a_kill() {
+        let path = knowledge_dir("kill");
+        let mut terms = Terms::new(&path).unwrap();
+        terms.put("first/1", term("first")).unwrap();
+        terms.put("second/1", term("second")).unwrap();
+        terms.delete("first/1").unwrap();
+        // no drop - as if the process was killed
+        std::mem::forget(terms);
+
+        let terms = Terms::new(&path).unwrap();
+        assert_eq!(terms.keys(), &vec!["second/1".to_string()]);
+        assert_eq!(terms.get("second/1").unwrap(), Some(term("second")));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_refused_changes() {
+        let path = knowledge_dir("refused");
+        let mut terms = Terms::new(&path).unwrap();
+        terms.put("first/1", term("first")).unwrap();
+        terms.put("second/1", term("second")).unwrap();
+
+        // renaming onto another term
+        assert!(matches!(
+            terms.put("first/1", term("second")),
+            Err(Error::AlreadyPresent(key)) if key == "second/1"
+        ));
+        assert!(matches!(
+            terms.delete("third/1"),
+            Err(Error::NotFound(key)) if key == "third/1"
+        ));
+        assert_eq!(terms.get("first/1").unwrap(), Some(term("first")));
+        assert_eq!(terms.get("second/1").unwrap(), Some(term("second")));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_transaction() {
+        let path = knowledge_dir("transaction");
+        let mut terms = Terms::new(&path).unwrap();
+        terms.put("first/1", term("first")).unwrap();
+
+        let mut transaction = terms.begin();
+        transaction.put("second/1", term("second"));
+        transaction.delete("first/1");
+        transaction.put("third/1", term("third"));
+        // fails only after the rest is staged
+        transaction.delete("first/1");
+        assert!(matches!(terms.commit(transaction), Err(Error::NotFound(_))));
+        assert_eq!(terms.keys(), &vec!["first/1".to_string()]);
+        assert_eq!(terms.get("first/1").unwrap(), Some(term("first")));
+
+        let mut transaction = terms.begin();
+        transaction.put("second/1", term("second"));
+        transaction.delete("first/1");
+        terms.commit(transaction).unwrap();
+        std::mem::forget(terms);
+
+        let terms = Terms::new(&path).unwrap();
+        assert_eq!(terms.keys(), &vec!["second/1".to_string()]);
+        assert_eq!(terms.get("second/1").unwrap(), Some(term("second")));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_transaction_deletes_later_entry_first() {
+        let path = knowledge_dir("delete_later_first");
+        let mut terms = Terms::new(&path).unwrap();
+        for name in ["first", "second", "third"] {
+            terms.put(&format!("{}/1", name), term(name)).unwrap();
         }
 
-        self.buffer.replace_range(
-            deleted_entry.offset..deleted_entry.offset + deleted_entry.len,
-            "",
+        let mut transaction = terms.begin();
+        transaction.delete("second/1");
+        transaction.delete("first/1");
+        terms.commit(transaction).unwrap();
+        assert_eq!(terms.keys(), &vec!["third/1".to_string()]);
+        assert_eq!(terms.get("third/1").unwrap(), Some(term("third")));
+        std::mem::forget(terms);
+
+        let terms = Terms::new(&path).unwrap();
+        assert_eq!(terms.get("third/1").unwrap(), Some(term("third")));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_transaction_deletes_last_entry_then_creates() {
+        let path = knowledge_dir("delete_last_then_create");
+        let mut terms = Terms::new(&path).unwrap();
+        terms.put("first/1", term("first")).unwrap();
+        terms.put("second/1", term("second")).unwrap();
+
+        let mut transaction = terms.begin();
+        transaction.delete("second/1");
+        transaction.put("third/1", term("third"));
+        transaction.put("first/1", term("renamed"));
+        terms.commit(transaction).unwrap();
+        assert_eq!(
+            terms.keys(),
+            &vec!["renamed/1".to_string(), "third/1".to_string()]
         );
+        assert_eq!(terms.get("renamed/1").unwrap(), Some(term("renamed")));
+        assert_eq!(terms.get("third/1").unwrap(), Some(term("third")));
+        std::mem::forget(terms);
+
+        let terms = Terms::new(&path).unwrap();
+        assert_eq!(terms.get("renamed/1").unwrap(), Some(term("renamed")));
+        assert_eq!(terms.get("third/1").unwrap(), Some(term("third")));
+        fs::remove_dir_all(&path).unwrap();
+    }
 
-        for descriptor_entry in self.descriptor[deleted_entry_idx + 1..].iter_mut() {
-            descriptor_entry.offset -= deleted_entry.len;
+    // what a consistent store looks like - checked after every step of test_long_sequence
+    fn assert_consistent(terms: &Terms, expected: &HashMap<String, FatTerm>) {
+        let mut offset = 0;
+        for (entry_idx, entry) in terms.descriptor.iter().enumerate() {
+            assert!(!entry.is_deleted);
+            assert_eq!(entry.offset, offset);
+            assert_eq!(terms.index[&entry.name], entry_idx);
+            assert_eq!(terms.keys[entry_idx], entry.name);
+            offset += entry.len;
+        }
+        assert_eq!(offset, terms.buffer.len());
+        assert_eq!(terms.index.len(), expected.len());
+        assert_eq!(terms.keys.len(), expected.len());
+        for (term_key, term) in expected {
+            assert_eq!(terms.get(term_key).unwrap().as_ref(), Some(term));
+        }
+    }
+
+    #[test]
+    fn test_long_sequence() {
+        let path = knowledge_dir("sequence");
+        let mut terms = Terms::new(&path).unwrap();
+        let mut expected: HashMap<String, FatTerm> = HashMap::new();
+
+        // a fixed linear congruential sequence - the same steps on every run
+        let mut seed: u64 = 42;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+        for step in 0..300 {
+            let name = format!("t{}", next(20));
+            let term_key = format!("{}/1", name);
+            match (next(3), expected.contains_key(&term_key)) {
+                (0, true) => {
+                    terms.delete(&term_key).unwrap();
+                    expected.remove(&term_key);
+                }
+                (_, true) => {
+                    // longer and shorter than before in turns
+                    let mut edited = term(&name);
+                    edited.meta.term.desc = "x".repeat(step % 7);
+                    terms.put(&term_key, edited.clone()).unwrap();
+                    expected.insert(term_key, edited);
+                }
+                (_, false) => {
+                    terms.put(&term_key, term(&name)).unwrap();
+                    expected.insert(term_key, term(&name));
+                }
+            }
+            assert_consistent(&terms, &expected);
         }
-        self.index.remove(term_name);
+        drop(terms);
 
-        let keys_idx = self.keys.iter().position(|name| name == term_name).unwrap();
-        self.keys.remove(keys_idx);
+        let terms = Terms::new(&path).unwrap();
+        assert_consistent(&terms, &expected);
+        fs::remove_dir_all(&path).unwrap();
     }
-}
 
-impl Load for Terms {
-    type Store = Terms;
+    #[test]
+    fn test_rebuild_descriptor() {
+        let path = knowledge_dir("rebuild");
+        let page = format!(
+            "{}% -broken it has no @see\n% @arg X the x\nbroken(a).\n{}",
+            term("first").encode(),
+            term("second").encode()
+        );
+        // checked out without the descriptor
+        fs::write(path.join(PAGE_NAME), &page).unwrap();
+        let terms = Terms::new(&path).unwrap();
+        assert_eq!(terms.keys(), &vec!["first/1", "broken/1", "second/1"]);
+        assert_eq!(terms.get("second/1").unwrap(), Some(term("second")));
+        assert!(terms.get("broken/1").is_err());
+        drop(terms);
+
+        // edited by hand - the offsets in the descriptor are off now
+        let page = format!(
+            "{}{}",
+            term("zeroth").encode(),
+            page.replace("broken", "third")
+        );
+        fs::write(path.join(PAGE_NAME), &page).unwrap();
+        let terms = Terms::new(&path).unwrap();
+        assert_eq!(
+            terms.keys(),
+            &vec!["zeroth/1", "first/1", "third/1", "second/1"]
+        );
+        assert_eq!(terms.get("first/1").unwrap(), Some(term("first")));
+        drop(terms);
+
+        // the rebuilt descriptor was written - it's picked up as is
+        let page_before = fs::read_to_string(path.join(PAGE_NAME)).unwrap();
+        let terms = Terms::new(&path).unwrap();
+        assert_eq!(terms.keys().len(), 4);
+        assert_eq!(
+            fs::read_to_string(path.join(PAGE_NAME)).unwrap(),
+            page_before
+        );
+        fs::remove_dir_all(&path).unwrap();
+    }
 
-    fn load(path: &Path) -> Self::Store {
-        Terms::new(path)
+    #[test]
+    fn test_duplicate_is_refused() {
+        let path = knowledge_dir("duplicate");
+        let page = term("first").encode() + &term("second").encode() + &term("first").encode();
+        fs::write(path.join(PAGE_NAME), &page).unwrap();
+        assert!(matches!(
+            Terms::new(&path),
+            Err(Error::Duplicate(key)) if key == "first/1"
+        ));
+        // nothing is written for a page.pl that has to be fixed first
+        assert_eq!(fs::read_to_string(path.join(PAGE_NAME)).unwrap(), page);
+        assert!(!path.join(DESCRIPTOR_NAME).exists());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_recover_interrupted_writes() {
+        let path = knowledge_dir("recover");
+        let mut terms = Terms::new(&path).unwrap();
+        terms.put("first/1", term("first")).unwrap();
+        drop(terms);
+
+        // interrupted before page.pl was replaced - the half written change is dropped
+        fs::write(temp_path(&path.join(PAGE_NAME)), "garbage").unwrap();
+        fs::write(temp_path(&path.join(DESCRIPTOR_NAME)), "garbage").unwrap();
+        let mut terms = Terms::new(&path).unwrap();
+        assert_eq!(terms.get("first/1").unwrap(), Some(term("first")));
+        assert!(!temp_path(&path.join(PAGE_NAME)).exists());
+        assert!(!temp_path(&path.join(DESCRIPTOR_NAME)).exists());
+
+        // interrupted right after page.pl was replaced - the descriptor is renamed on load
+        terms.put("second/1", term("second")).unwrap();
+        std::mem::forget(terms);
+        let descriptor_path = path.join(DESCRIPTOR_NAME);
+        fs::rename(&descriptor_path, temp_path(&descriptor_path)).unwrap();
+        let terms = Terms::new(&path).unwrap();
+        assert_eq!(terms.get("second/1").unwrap(), Some(term("second")));
+        fs::remove_dir_all(&path).unwrap();
     }
 }
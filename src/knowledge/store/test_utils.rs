@@ -0,0 +1,40 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::knowledge::model::fat_term::{parse_fat_term, FatTerm};
+
+// Fixtures shared by the tests of the stores and of what's built on them
+
+// a one-arity term with a single "name(fact)." fact, the way page.pl has it
+pub(crate) fn raw_term(name: &str, fact: &str) -> String {
+    format!(
+        "% -{} some description\n% @arg X the x\n% @see\n{}({}).\n",
+        name, name, fact
+    )
+}
+
+pub(crate) fn term_with_fact(name: &str, fact: &str) -> FatTerm {
+    parse_fat_term(&raw_term(name, fact))
+        .expect("the fixture parses")
+        .1
+}
+
+pub(crate) fn term(name: &str) -> FatTerm {
+    term_with_fact(name, "a")
+}
+
+// a fresh empty directory for every call - the tests run in parallel, so the process id alone
+// isn't enough to tell their directories apart
+pub(crate) fn knowledge_dir(test_name: &str) -> PathBuf {
+    static DIRS: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "its_logical_{}_{}_{}",
+        test_name,
+        std::process::id(),
+        DIRS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).expect("the temp dir is writable");
+    path
+}
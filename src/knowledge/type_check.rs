@@ -0,0 +1,205 @@
+use thiserror::Error;
+
+use super::{
+    model::{
+        comment::{arg_type::ArgType, name_description::NameDescription},
+        fat_term::FatTerm,
+        term::{arg::Arg, args_binding::ArgsBinding},
+    },
+    store::{Get, Keys},
+};
+
+// Checks the facts of terms against the types declared in their "% @arg Name:type" lines. Args
+// without a type accept anything.
+
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum TypeError {
+    #[error("{arg_name} should be {expected}, found {found}")]
+    Mismatch {
+        arg_name: String,
+        expected: ArgType,
+        found: String,
+    },
+    #[error("{arg_name} refers to {term_key}, but {found} is not among its facts")]
+    DanglingReference {
+        arg_name: String,
+        term_key: String,
+        found: String,
+    },
+    // the referenced term is missing or can't be read
+    #[error("{arg_name} refers to {term_key}, which can't be found")]
+    UnknownReference { arg_name: String, term_key: String },
+}
+
+#[derive(Error, Debug, PartialEq, Clone)]
+#[error("{term_key}, fact {}: {error}", .fact_idx + 1)]
+pub struct FactTypeError {
+    pub term_key: String,
+    // the position of the fact within the term
+    pub fact_idx: usize,
+    pub error: TypeError,
+}
+
+// `args` are the declared args of the term the fact belongs to
+pub fn check_fact(
+    args: &[NameDescription],
+    fact: &ArgsBinding,
+    store: &impl Get,
+) -> Vec<TypeError> {
+    let mut errors = vec![];
+    for (arg, value) in args.iter().zip(fact.binding.iter()) {
+        let Some(arg_type) = &arg.arg_type else {
+            continue;
+        };
+        if !arg_type.accepts(value) {
+            errors.push(TypeError::Mismatch {
+                arg_name: arg.name.clone(),
+                expected: arg_type.clone(),
+                found: value.encode(),
+            });
+            continue;
+        }
+        if let ArgType::Reference(term_key) = arg_type {
+            if !matches!(value, Arg::Variable(_)) {
+                errors.extend(check_reference(&arg.name, term_key, value, store));
+            }
+        }
+    }
+    errors
+}
+
+pub fn check_term(term: &FatTerm, store: &impl Get) -> Vec<FactTypeError> {
+    let term_key = term.key();
+    term.term
+        .facts
+        .iter()
+        .enumerate()
+        .flat_map(|(fact_idx, fact)| {
+            check_fact(&term.meta.args, fact, store)
+                .into_iter()
+                .map(move |error| (fact_idx, error))
+        })
+        .map(|(fact_idx, error)| FactTypeError {
+            term_key: term_key.clone(),
+            fact_idx,
+            error,
+        })
+        .collect()
+}
+
+// terms that can't be parsed are skipped - their parse error shows up when they are opened
+pub fn check_store(store: &(impl Get + Keys)) -> Vec<FactTypeError> {
+    store
+        .keys()
+        .iter()
+        .filter_map(|term_key| store.get(term_key).ok().flatten())
+        .flat_map(|term| check_term(&term, store))
+        .collect()
+}
+
+fn check_reference(
+    arg_name: &str,
+    term_key: &str,
+    value: &Arg,
+    store: &impl Get,
+) -> Option<TypeError> {
+    let Ok(Some(referenced)) = store.get(term_key) else {
+        return Some(TypeError::UnknownReference {
+            arg_name: arg_name.to_owned(),
+            term_key: term_key.to_owned(),
+        });
+    };
+    let is_present = referenced
+        .term
+        .facts
+        .iter()
+        .any(|fact| fact.binding.first() == Some(value));
+    if is_present {
+        return None;
+    }
+    Some(TypeError::DanglingReference {
+        arg_name: arg_name.to_owned(),
+        term_key: term_key.to_owned(),
+        found: value.encode(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::knowledge::{model::fat_term::parse_fat_term, store::in_memory::InMemoryTerms};
+
+    fn parse(input: &str) -> FatTerm {
+        parse_fat_term(input).unwrap().1
+    }
+
+    #[test]
+    fn test_check_store() {
+        let person = parse(
+            r"% -person someone known
+% @arg Name the name
+% @see owns
+person(bob).
+person(ann).
+",
+        );
+        let owns = parse(
+            r"% -owns who owns what since when
+% @arg Owner:ref(person/1) the owner
+% @arg Thing:enum(car|bike) the thing
+% @arg Since:date when it was bought
+% @arg Price:integer what it cost
+% @see
+owns(bob,car,'2020-01-31',100).
+owns(tom,bike,'2020-02-30',_).
+owns(ann,boat,'2021-06-01',9.99).
+",
+        );
+        let store = InMemoryTerms::new(HashMap::from([(person.key(), person), (owns.key(), owns)]));
+
+        let errors = check_store(&store);
+        assert_eq!(
+            errors,
+            vec![
+                FactTypeError {
+                    term_key: "owns/4".to_string(),
+                    fact_idx: 1,
+                    error: TypeError::DanglingReference {
+                        arg_name: "Owner".to_string(),
+                        term_key: "person/1".to_string(),
+                        found: "tom".to_string(),
+                    },
+                },
+                FactTypeError {
+                    term_key: "owns/4".to_string(),
+                    fact_idx: 1,
+                    error: TypeError::Mismatch {
+                        arg_name: "Since".to_string(),
+                        expected: ArgType::Date,
+                        found: "'2020-02-30'".to_string(),
+                    },
+                },
+                FactTypeError {
+                    term_key: "owns/4".to_string(),
+                    fact_idx: 2,
+                    error: TypeError::Mismatch {
+                        arg_name: "Thing".to_string(),
+                        expected: ArgType::Enum(vec!["car".to_string(), "bike".to_string()]),
+                        found: "boat".to_string(),
+                    },
+                },
+                FactTypeError {
+                    term_key: "owns/4".to_string(),
+                    fact_idx: 2,
+                    error: TypeError::Mismatch {
+                        arg_name: "Price".to_string(),
+                        expected: ArgType::Integer,
+                        found: "9.99".to_string(),
+                    },
+                },
+            ]
+        );
+    }
+}
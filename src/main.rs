@@ -3,7 +3,9 @@
 
 use tracing::Level;
 
-use its_logical::knowledge::store::persistent::TermsWithEngine;
+use its_logical::knowledge::store::{
+    embedded::EmbeddedTerms, in_memory::InMemoryTerms, module::ModuleTerms,
+};
 
 mod app;
 mod change_propagation;
@@ -26,18 +28,91 @@ fn main() -> eframe::Result<()> {
     let native_options = eframe::NativeOptions::default();
     let knowledge_path = env::var("KNOWLEDGE_PATH").unwrap_or("~/knowledge".to_string());
     let knowledge_path = PathBuf::from(knowledge_path);
+    // "its_logical lint" checks the knowledge without starting the UI - loading it may still write
+    // to the module: a missing or outdated descriptor is rebuilt and a one-file-per-term module
+    // has its page.pl split
+    if env::args().nth(1).as_deref() == Some("lint") {
+        lint(&knowledge_path);
+    }
+    // "its_logical export" writes page.pl from the database of "--embedded"
+    if env::args().nth(1).as_deref() == Some("export") {
+        export(&knowledge_path);
+    }
+    // "its_logical --in-memory" works on a copy of the knowledge - nothing is written back
+    if env::args().any(|arg| arg == "--in-memory") {
+        return eframe::run_native(
+            "It's Logical",
+            native_options,
+            Box::new(|cc| {
+                Ok(Box::new(ItsLogicalApp::<InMemoryTerms>::new(
+                    cc,
+                    knowledge_path,
+                )?))
+            }),
+        );
+    }
+    // "its_logical --embedded" keeps the knowledge in a database - for big knowledge bases
+    if env::args().any(|arg| arg == "--embedded") {
+        return eframe::run_native(
+            "It's Logical",
+            native_options,
+            Box::new(|cc| {
+                Ok(Box::new(ItsLogicalApp::<EmbeddedTerms>::new(
+                    cc,
+                    knowledge_path,
+                )?))
+            }),
+        );
+    }
     eframe::run_native(
         "It's Logical",
         native_options,
         Box::new(|cc| {
-            Ok(Box::new(ItsLogicalApp::<TermsWithEngine>::new(
+            Ok(Box::new(ItsLogicalApp::<ModuleTerms>::new(
                 cc,
                 knowledge_path,
-            )))
+            )?))
         }),
     )
 }
 
+// prints the lint findings and exits - with a failure when any of them is an error
+#[cfg(not(target_arch = "wasm32"))]
+fn lint(knowledge_path: &std::path::Path) -> ! {
+    use its_logical::knowledge::{
+        lint::{lint_store, Severity},
+        store::Load,
+    };
+
+    let terms = match ModuleTerms::load(knowledge_path) {
+        Ok(terms) => terms,
+        Err(e) => {
+            eprintln!("can't load {}: {}", knowledge_path.display(), e);
+            std::process::exit(2);
+        }
+    };
+    let diagnostics = lint_store(&terms);
+    for diagnostic in &diagnostics {
+        println!("{:?}: {}", diagnostic.severity(), diagnostic);
+    }
+    let has_errors = diagnostics.iter().any(|d| d.severity() == Severity::Error);
+    std::process::exit(if has_errors { 1 } else { 0 });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn export(knowledge_path: &std::path::Path) -> ! {
+    use its_logical::knowledge::store::Load;
+
+    let exported = EmbeddedTerms::load(knowledge_path)
+        .map_err(|e| e.to_string())
+        .and_then(|terms| terms.export().map_err(|e| e.to_string()));
+    if let Err(e) = exported {
+        eprintln!("can't export {}: {}", knowledge_path.display(), e);
+        std::process::exit(2);
+    }
+    std::process::exit(0);
+}
+
 // when compiling to web using trunk.
 #[cfg(target_arch = "wasm32")]
 fn main() {
@@ -18,11 +18,11 @@ where
     T: NamedTerm,
     K: TwoPhaseTerm,
 {
-    fn get(&self, term_name: &str) -> Option<FatTerm> {
-        self.get(term_name).map(|term| match term {
+    fn get(&self, term_key: &str) -> Result<Option<FatTerm>, knowledge::store::Error> {
+        Ok(self.get(term_key).map(|term| match term {
             TermHolder::Normal(t) => t.term(),
             TermHolder::TwoPhase(t) => t.term(),
-        })
+        }))
     }
 }
 
@@ -34,7 +34,7 @@ where
     // should not be called when the change source is a part of a Two Phase Commit
     // TODO: return error instead of panic
     pub(crate) fn apply_automatic_change(&mut self, change: &Change) {
-        if let Some(changed) = self.get_mut(&change.original().meta.term.name) {
+        if let Some(changed) = self.get_mut(&change.original().key()) {
             let changed_update = |_: &FatTerm| -> FatTerm { change.changed().to_owned() };
             match changed {
                 TermHolder::Normal(t) => t.apply(changed_update),
@@ -46,7 +46,8 @@ where
         let update_fn = |in_term: &FatTerm| -> FatTerm {
             in_term
                 .apply(change)
-                .get(&in_term.meta.term.name)
+                .expect("a single term is read without a store")
+                .get(&in_term.key())
                 // the change might not affect the in_term so it needs to be returned as is
                 .unwrap_or(in_term)
                 .to_owned()
@@ -62,22 +63,25 @@ where
     }
 
     pub(crate) fn apply_automatic_deletion(&mut self, term: &FatTerm) {
-        let changed_by_deletion = term.apply_deletion(self);
+        let changed_by_deletion = term
+            .apply_deletion(self)
+            .expect("the cache is read without a store");
         let update = |t: &FatTerm| -> FatTerm {
             term.apply_deletion(t)
-                .get(&t.meta.term.name)
+                .expect("a single term is read without a store")
+                .get(&t.key())
                 .unwrap_or(t)
                 .to_owned()
         };
-        for term_name in changed_by_deletion.keys() {
-            if let Some(cached_term) = self.get_mut(term_name) {
+        for term_key in changed_by_deletion.keys() {
+            if let Some(cached_term) = self.get_mut(term_key) {
                 match cached_term {
                     TermHolder::Normal(s) => s.apply(update),
                     TermHolder::TwoPhase(s) => s.apply(update),
                 }
             }
         }
-        self.remove(&term.meta.term.name);
+        self.remove(&term.key());
     }
 }
 
@@ -112,8 +116,8 @@ mod tests {
             }
         }
 
-        fn name(&self) -> String {
-            self.term.meta.term.name.clone()
+        fn key(&self) -> String {
+            self.term.key()
         }
 
         fn term(&self) -> FatTerm {
@@ -165,11 +169,10 @@ mod tests {
         fn create_affected() -> FatTerm {
             let mut affected = FatTerm::default();
             affected.meta.term.name = "affected".to_string();
-            affected.term.rules.push(
-                parse_rule("affected(Some_var):-original(someConst).")
-                    .unwrap()
-                    .1,
-            );
+            affected
+                .term
+                .rules
+                .push(parse_rule("affected(Some_var):-original.").unwrap().1);
             affected
         }
 
@@ -206,7 +209,7 @@ mod tests {
             let mut affected = create_affected();
             affected.meta.term.name = "affected_promoted".to_string();
             cache.push(&affected);
-            cache.promote("affected_promoted");
+            cache.promote("affected_promoted/0");
         }
 
         pub(crate) fn add_unaffected(cache: &mut TermsCache<ApplyMock, ApplyMock>) -> FatTerm {
@@ -222,7 +225,7 @@ mod tests {
             let mut unaffected = FatTerm::default();
             unaffected.meta.term.name = "unaffected_promoted".to_string();
             cache.push(&unaffected);
-            cache.promote("unaffected_promoted");
+            cache.promote("unaffected_promoted/0");
             unaffected
         }
     }
@@ -240,7 +243,7 @@ mod tests {
         changed.meta.term.name = "changed".to_string();
 
         let change = Change::new(original, &[], changed);
-        cache.promote("original");
+        cache.promote("original/0");
 
         cache.apply_automatic_change(&change);
     }
@@ -253,7 +256,7 @@ mod tests {
         let change = setup::get_change(None);
         cache.apply_automatic_change(&change);
 
-        let unaffected = cache.get("unaffected").expect("should still be present");
+        let unaffected = cache.get("unaffected/0").expect("should still be present");
         match unaffected {
             TermHolder::Normal(t) => {
                 assert_eq!(
@@ -274,7 +277,7 @@ mod tests {
         cache.apply_automatic_change(&change);
 
         let unaffected_promoted = cache
-            .get("unaffected_promoted")
+            .get("unaffected_promoted/0")
             .expect("should still be present");
         match unaffected_promoted {
             TermHolder::Normal(_) => unreachable!("should not be downgraded"),
@@ -290,14 +293,15 @@ mod tests {
         let mut cache = TermsCache::<ApplyMock, ApplyMock>::default();
         setup::add_affected(&mut cache);
 
-        let change = setup::get_change(Some("affected"));
+        let change = setup::get_change(Some("affected/0"));
         cache.apply_automatic_change(&change);
 
-        let affected = cache.get("affected").expect("should still be present");
+        let affected = cache.get("affected/0").expect("should still be present");
         match affected {
             TermHolder::Normal(t) => {
                 assert_eq!(
-                    t.term.term.rules[0].body[0].name, "changed",
+                    t.term.term.rules[0].body_terms()[0].name,
+                    "changed",
                     "affected term should be changed accordingly"
                 )
             }
@@ -310,16 +314,17 @@ mod tests {
         let mut cache = TermsCache::<ApplyMock, ApplyMock>::default();
         setup::add_affected_promoted(&mut cache);
 
-        let change = setup::get_change(Some("affected_promoted"));
+        let change = setup::get_change(Some("affected_promoted/0"));
         cache.apply_automatic_change(&change);
 
         let affected = cache
-            .get("affected_promoted")
+            .get("affected_promoted/0")
             .expect("should still be present");
         match affected {
             TermHolder::Normal(_) => unreachable!("should not be downgraded"),
             TermHolder::TwoPhase(t) => assert_eq!(
-                t.term.term.rules[0].body[0].name, "changed",
+                t.term.term.rules[0].body_terms()[0].name,
+                "changed",
                 "affected term should be changed accordingly"
             ),
         }
@@ -329,16 +334,16 @@ mod tests {
     fn apply_change_when_source_is_present() {
         let mut cache = TermsCache::<ApplyMock, ApplyMock>::default();
 
-        let change = setup::get_change(Some("affected_promoted"));
+        let change = setup::get_change(Some("affected_promoted/0"));
         cache.push(change.original());
 
         cache.apply_automatic_change(&change);
 
         assert!(
-            cache.get("original").is_none(),
+            cache.get("original/0").is_none(),
             "original was changed to changed"
         );
-        let change_source = cache.get("changed").expect("should now be renamed");
+        let change_source = cache.get("changed/0").expect("should now be renamed");
         match change_source {
             TermHolder::Normal(t) => assert_eq!(t.term.meta.term.name, "changed"),
             TermHolder::TwoPhase(_) => unreachable!("should not be promoted"),
@@ -354,7 +359,7 @@ mod tests {
 
         cache.apply_automatic_deletion(&to_be_deleted);
 
-        let unaffected = cache.get("unaffected").expect("should still be present");
+        let unaffected = cache.get("unaffected/0").expect("should still be present");
         match unaffected {
             TermHolder::Normal(t) => {
                 assert_eq!(
@@ -376,7 +381,7 @@ mod tests {
         cache.apply_automatic_deletion(&to_be_deleted);
 
         let unaffected_promoted = cache
-            .get("unaffected_promoted")
+            .get("unaffected_promoted/0")
             .expect("should still be present");
         match unaffected_promoted {
             TermHolder::Normal(_) => unreachable!("should not be downgraded"),
@@ -392,10 +397,10 @@ mod tests {
         let mut cache = TermsCache::<ApplyMock, ApplyMock>::default();
         setup::add_affected(&mut cache);
 
-        let to_be_deleted = setup::get_to_be_deleted(Some("affected"));
+        let to_be_deleted = setup::get_to_be_deleted(Some("affected/0"));
         cache.apply_automatic_deletion(&to_be_deleted);
 
-        let affected = cache.get("affected").expect("should still be present");
+        let affected = cache.get("affected/0").expect("should still be present");
         match affected {
             TermHolder::Normal(t) => {
                 assert!(
@@ -412,11 +417,11 @@ mod tests {
         let mut cache = TermsCache::<ApplyMock, ApplyMock>::default();
         setup::add_affected_promoted(&mut cache);
 
-        let to_be_deleted = setup::get_to_be_deleted(Some("affected_promoted"));
+        let to_be_deleted = setup::get_to_be_deleted(Some("affected_promoted/0"));
         cache.apply_automatic_deletion(&to_be_deleted);
 
         let affected = cache
-            .get("affected_promoted")
+            .get("affected_promoted/0")
             .expect("should still be present");
         match affected {
             TermHolder::Normal(_) => unreachable!("should not be downgraded"),
@@ -436,6 +441,6 @@ mod tests {
         cache.push(&to_be_deleted);
         cache.apply_automatic_deletion(&to_be_deleted);
 
-        assert!(cache.get("original").is_none());
+        assert!(cache.get("original/0").is_none());
     }
 }
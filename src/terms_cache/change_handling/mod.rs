@@ -27,32 +27,34 @@ where
     T: NamedTerm,
     K: TwoPhaseTerm<Creator = T>,
 {
-    pub(crate) fn finish_commit(&mut self) -> HashMap<String, FinishedCommitResult> {
+    // what finishing the commit writes, by the keys the terms had before it - the cache is left
+    // as it is until the store has taken it
+    pub(crate) fn commit_results(&self) -> HashMap<String, FinishedCommitResult> {
         let mut new_term_versions = HashMap::new();
-        let mut removed_terms_indices = Vec::new();
-        for (idx, term) in self.terms.iter_mut().enumerate() {
+        for term in &self.terms {
             if let TermHolder::TwoPhase(t) = term {
-                let term_name_before_changes = t.before_changes().meta.term.name;
-                if t.in_deletion() {
-                    removed_terms_indices.push(idx);
-                    new_term_versions
-                        .insert(term_name_before_changes, FinishedCommitResult::Deleted);
+                let result = if t.in_deletion() {
+                    FinishedCommitResult::Deleted
                 } else {
-                    let current_version = t.term();
-
-                    *term = TermHolder::Normal(T::new(&current_version));
-                    new_term_versions.insert(
-                        term_name_before_changes,
-                        FinishedCommitResult::Changed(current_version),
-                    );
-                }
+                    FinishedCommitResult::Changed(t.term())
+                };
+                new_term_versions.insert(t.before_changes().key(), result);
             }
         }
-        for i in removed_terms_indices.iter().rev() {
-            self.terms.remove(*i);
-        }
         new_term_versions
     }
+
+    pub(crate) fn finish_commit(&mut self) {
+        self.terms.retain(|term| match term {
+            TermHolder::TwoPhase(t) => !t.in_deletion(),
+            TermHolder::Normal(_) => true,
+        });
+        for term in &mut self.terms {
+            if let TermHolder::TwoPhase(t) = term {
+                *term = TermHolder::Normal(T::new(&t.term()));
+            }
+        }
+    }
     pub(crate) fn revert_commit(&mut self) {
         for term in &mut self.terms {
             if let TermHolder::TwoPhase(t) = term {
@@ -79,7 +81,7 @@ where
         let mut need_to_transfer_to_commit = false;
         for term in &self.terms {
             if let super::TermHolder::TwoPhase(t) = term {
-                if newly_mentioned_terms.contains(&t.name()) {
+                if newly_mentioned_terms.contains(&t.key()) {
                     need_to_transfer_to_commit = true;
                     break;
                 }
@@ -88,18 +90,18 @@ where
         if !need_to_transfer_to_commit {
             return;
         }
-        let updated_term_name = if is_automatic {
-            // the term has already been updated to using its potentially changed name
-            change.changed().meta.term.name.clone()
+        let updated_term_key = if is_automatic {
+            // the term has already been updated to using its potentially changed key
+            change.changed().key()
         } else {
-            // the term is still referred to by its old name
-            change.original().meta.term.name.clone()
+            // the term is still referred to by its old key
+            change.original().key()
         };
 
-        self.promote(&updated_term_name);
+        self.promote(&updated_term_key);
 
         let (mut updated_term, others): (Vec<_>, Vec<_>) =
-            self.iter_mut().partition(|x| x.name() == updated_term_name);
+            self.iter_mut().partition(|x| x.key() == updated_term_key);
 
         let updated_term = match updated_term.get_mut(0) {
             Some(TermHolder::TwoPhase(t)) => t,
@@ -108,7 +110,7 @@ where
 
         for term in others
             .iter()
-            .filter(|x| newly_mentioned_terms.contains(&x.name()))
+            .filter(|x| newly_mentioned_terms.contains(&x.key()))
         {
             if let super::TermHolder::TwoPhase(mentioned_term) = term {
                 let mentioned_term_change = mentioned_term.current_change();
@@ -116,12 +118,13 @@ where
                 if let Some(with_applied_change) = updated_term
                     .term()
                     .apply(&mentioned_term_change)
-                    .get(&change.changed().meta.term.name)
+                    .expect("a single term is read without a store")
+                    .get(&change.changed().key())
                 {
                     updated_term.push_for_confirmation(
                         &[],
                         with_applied_change,
-                        &mentioned_term.name(),
+                        &mentioned_term.key(),
                     );
                     fix_approvals(
                         updated_term.two_phase_commit(),
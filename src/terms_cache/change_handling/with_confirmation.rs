@@ -11,9 +11,19 @@ use its_logical::{
     },
     knowledge::{self, model::fat_term::FatTerm},
 };
+use thiserror::Error;
 
 use super::{two_phase_commit::TwoPhaseCommit, NamedTerm, TermsCache, TwoPhaseTerm};
 
+#[derive(Error, Debug)]
+pub(crate) enum Error {
+    #[error("there is a term that is not ready to be included in a 2 phase commit")]
+    NotReady,
+    // one of the affected terms can't be read - nothing is pushed for confirmation for it
+    #[error(transparent)]
+    Store(#[from] knowledge::store::Error),
+}
+
 pub(crate) trait Apply {
     fn push_for_confirmation(
         &mut self,
@@ -36,7 +46,7 @@ where
         // the TermsCache, so they would need to be cached during this call
         store: &impl knowledge::store::Get,
         change: &Change,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), Error> {
         let (mentioned, referred_by) = change.affects();
         let mut affected_by_change = HashSet::with_capacity(mentioned.len() + referred_by.len());
         affected_by_change.extend(mentioned);
@@ -44,37 +54,37 @@ where
         let affected_by_change: Vec<String> = affected_by_change.into_iter().collect();
 
         if !self.are_ready_for_change(&affected_by_change) {
-            return Err("There is a term that is not ready to be included in a 2 phase commit");
+            return Err(Error::NotReady);
         }
 
-        self.push_affected(&affected_by_change, store);
+        self.push_affected(&affected_by_change, store)?;
 
-        let all_affected_changed = self.apply(change);
+        let all_affected_changed = self.apply(change)?;
 
         let original = change.original();
 
-        if self.get(&original.meta.term.name).is_none() {
+        if self.get(&original.key()).is_none() {
             self.push(original);
         }
 
         let change_source_two_phase_commit = {
             let change_source = self
-                .promote(&original.meta.term.name)
+                .promote(&original.key())
                 .expect("guaranteed to be opened above");
             change_source.push_for_confirmation(
                 change.arg_changes(),
                 change.changed(),
-                &original.meta.term.name,
+                &original.key(),
             );
             change_source.two_phase_commit().to_owned()
         };
 
         self.push_to_changed(
-            &original.meta.term.name,
+            &original.key(),
             &change_source_two_phase_commit,
             store,
             all_affected_changed,
-        );
+        )?;
         Ok(())
     }
 
@@ -82,28 +92,28 @@ where
         &mut self,
         deleted_term: &FatTerm,
         store: &impl knowledge::store::Get,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), Error> {
         let affected_by_deletion = deleted_term.affects();
 
         if !self.are_ready_for_change(affected_by_deletion) {
-            return Err("There is a term that is not ready to be included in a 2 phase commit");
+            return Err(Error::NotReady);
         }
 
-        self.push_affected(affected_by_deletion, store);
+        self.push_affected(affected_by_deletion, store)?;
 
-        let changed_by_deletion = deleted_term.apply_deletion(self);
+        let changed_by_deletion = deleted_term.apply_deletion(self)?;
         let deleted_two_phase_commit = self
-            .promote(&deleted_term.meta.term.name)
+            .promote(&deleted_term.key())
             .expect("it must be opened as it was just deleted")
             .two_phase_commit()
             .to_owned();
 
         self.push_to_changed(
-            &deleted_term.meta.term.name,
+            &deleted_term.key(),
             &deleted_two_phase_commit,
             store,
             changed_by_deletion,
-        );
+        )?;
         Ok(())
     }
 
@@ -117,14 +127,24 @@ where
             })
     }
 
-    fn push_affected(&mut self, affected: &[String], store: &impl knowledge::store::Get) {
+    fn push_affected(
+        &mut self,
+        affected: &[String],
+        store: &impl knowledge::store::Get,
+    ) -> Result<(), knowledge::store::Error> {
+        // everything is read before anything is cached, so a failed read leaves the cache as it was
+        let mut stored = vec![];
         for affected_term in affected {
             if self.get(affected_term).is_none() {
-                if let Some(t) = store.get(affected_term) {
-                    self.push(&t);
+                if let Some(t) = store.get(affected_term)? {
+                    stored.push(t);
                 }
             }
         }
+        for t in &stored {
+            self.push(t);
+        }
+        Ok(())
     }
 
     fn push_to_changed(
@@ -133,14 +153,13 @@ where
         source_two_phase_commit: &Rc<RefCell<TwoPhaseCommit>>,
         store: &impl knowledge::store::Get,
         changed: HashMap<String, FatTerm>,
-    ) {
+    ) -> Result<(), knowledge::store::Error> {
         for (term_name, changed_term) in changed {
             if self.get(&term_name).is_none() {
-                self.push(
-                    &store
-                        .get(&term_name)
-                        .expect("this term has come from the knowledge store"),
-                );
+                let stored = store
+                    .get(&term_name)?
+                    .ok_or_else(|| knowledge::store::Error::NotFound(term_name.clone()))?;
+                self.push(&stored);
             }
 
             let changed_two_phase_commit = self.promote(&term_name).expect("term was just pushed");
@@ -151,5 +170,6 @@ where
                 source_two_phase_commit,
             )
         }
+        Ok(())
     }
 }
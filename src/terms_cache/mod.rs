@@ -7,7 +7,8 @@ use self::change_handling::two_phase_commit::TwoPhaseCommit;
 pub(crate) mod change_handling;
 pub(crate) trait NamedTerm {
     fn new(term: &FatTerm) -> Self;
-    fn name(&self) -> String;
+    // "name/arity" - see FatTerm::key
+    fn key(&self) -> String;
     fn term(&self) -> FatTerm;
 }
 pub(crate) trait TwoPhaseTerm: NamedTerm {
@@ -26,10 +27,10 @@ pub(crate) enum TermHolder<T: NamedTerm, K: TwoPhaseTerm> {
 }
 
 impl<T: NamedTerm, K: TwoPhaseTerm> TermHolder<T, K> {
-    fn name(&self) -> String {
+    fn key(&self) -> String {
         match self {
-            TermHolder::Normal(s) => s.name(),
-            TermHolder::TwoPhase(s) => s.name(),
+            TermHolder::Normal(s) => s.key(),
+            TermHolder::TwoPhase(s) => s.key(),
         }
     }
 }
@@ -48,8 +49,8 @@ impl<T: NamedTerm, K: TwoPhaseTerm> Default for TermsCache<T, K> {
 // prematurely-optimised aspect of the TermsCache, for when users remember the idx and query directly
 // by index to avoid iteration
 impl<T: NamedTerm, K: TwoPhaseTerm> TermsCache<T, K> {
-    pub(crate) fn find(&self, term_name: &str) -> Option<usize> {
-        self.terms.iter().position(|x| x.name() == term_name)
+    pub(crate) fn find(&self, term_key: &str) -> Option<usize> {
+        self.terms.iter().position(|x| x.key() == term_key)
     }
 
     pub(crate) fn get_by_idx(&self, idx: usize) -> Option<&TermHolder<T, K>> {
@@ -69,22 +70,22 @@ where
         self.terms.push(TermHolder::Normal(NamedTerm::new(term)));
     }
 
-    pub(crate) fn get(&self, term_name: &str) -> Option<&TermHolder<T, K>> {
-        if let Some(term_idx) = self.terms.iter().position(|x| x.name() == term_name) {
+    pub(crate) fn get(&self, term_key: &str) -> Option<&TermHolder<T, K>> {
+        if let Some(term_idx) = self.terms.iter().position(|x| x.key() == term_key) {
             return Some(&self.terms[term_idx]);
         }
         None
     }
 
-    pub(crate) fn get_mut(&mut self, term_name: &str) -> Option<&mut TermHolder<T, K>> {
-        if let Some(term_idx) = self.terms.iter().position(|x| x.name() == term_name) {
+    pub(crate) fn get_mut(&mut self, term_key: &str) -> Option<&mut TermHolder<T, K>> {
+        if let Some(term_idx) = self.terms.iter().position(|x| x.key() == term_key) {
             return Some(&mut self.terms[term_idx]);
         }
         None
     }
 
-    pub(crate) fn remove(&mut self, term_name: &str) -> Option<TermHolder<T, K>> {
-        if let Some(term_idx) = self.terms.iter().position(|x| x.name() == term_name) {
+    pub(crate) fn remove(&mut self, term_key: &str) -> Option<TermHolder<T, K>> {
+        if let Some(term_idx) = self.terms.iter().position(|x| x.key() == term_key) {
             return Some(self.terms.remove(term_idx));
         }
         None
@@ -104,8 +105,8 @@ where
     T: NamedTerm,
     K: TwoPhaseTerm + TwoPhaseTerm<Creator = T>,
 {
-    pub(crate) fn promote(&mut self, term_name: &str) -> Option<&mut K> {
-        if let Some(term_idx) = self.terms.iter().position(|x| x.name() == term_name) {
+    pub(crate) fn promote(&mut self, term_key: &str) -> Option<&mut K> {
+        if let Some(term_idx) = self.terms.iter().position(|x| x.key() == term_key) {
             let screens_len = self.terms.len();
             let to_be_promoted = self.terms.swap_remove(term_idx);
             match to_be_promoted {
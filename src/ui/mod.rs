@@ -1,10 +1,16 @@
+use its_logical::knowledge::history::History;
+use its_logical::knowledge::integrity::{self, IntegrityReport};
+use its_logical::knowledge::lint::{self, Severity};
 use its_logical::knowledge::model::fat_term::FatTerm;
 use its_logical::knowledge::store::{Load, TermsStore};
-use std::path::PathBuf;
+use its_logical::knowledge::type_check;
+use std::path::{Path, PathBuf};
 
 use egui::Context;
 use tracing::debug;
 
+use crate::change_propagation;
+
 mod load_module_menu;
 mod tabs;
 mod term_screen;
@@ -16,6 +22,16 @@ pub struct App<T: TermsStore> {
     tabs: tabs::Tabs,
     term_list: terms_list::TermList,
     terms: T,
+    // the git repository of the loaded module - None when the module is not one
+    history: Option<History>,
+    // shown in the side panel until the next successful load or term selection
+    error: Option<String>,
+    // the outcome of the last "Check facts" - None when it was never run
+    type_errors: Option<Vec<String>>,
+    // the outcome of the last "Lint" - None when it was never run
+    diagnostics: Option<Vec<lint::Diagnostic>>,
+    // the outcome of the last "Check @see" - None when it was never run or after a repair
+    integrity: Option<IntegrityReport>,
 }
 
 impl<T> App<T>
@@ -23,15 +39,36 @@ where
     T: TermsStore + Load<Store = T>,
 {
     pub fn new(terms: T, knowledge_path: PathBuf) -> Self {
+        let (history, error) = open_history(&knowledge_path);
         Self {
             tabs: tabs::Tabs::default(),
             term_list: terms_list::TermList::new(),
             terms,
+            history,
             load_menu: load_module_menu::LoadModuleMenu::new(knowledge_path),
+            error,
+            type_errors: None,
+            diagnostics: None,
+            integrity: None,
         }
     }
 }
 
+// a module whose repository can't be opened still loads - its changes just aren't committed
+fn open_history(module_path: &Path) -> (Option<History>, Option<String>) {
+    match History::open(module_path) {
+        Ok(history) => (history, None),
+        Err(e) => (
+            None,
+            Some(format!(
+                "Changes to {} won't be committed to git: {}",
+                module_path.display(),
+                e
+            )),
+        ),
+    }
+}
+
 impl<T> App<T>
 where
     T: TermsStore + Load<Store = T>,
@@ -42,16 +79,16 @@ where
                 match output {
                     terms_list::TermListOutput::AddTerm(new_term_name) => {
                         let new_term = FatTerm::default();
-                        if self.tabs.select(&new_term.meta.term.name) {
+                        if self.tabs.select(&new_term.key()) {
                             debug!("unfinished term creation present");
                             return;
                         }
                         self.tabs.push(&new_term);
-                        self.tabs.select(&new_term.meta.term.name);
+                        self.tabs.select(&new_term.key());
 
                         let new_term_screen = self
                             .tabs
-                            .get_mut(&new_term.meta.term.name)
+                            .get_mut(&new_term.key())
                             .expect("the newly created term was just added");
 
                         match new_term_screen {
@@ -66,8 +103,18 @@ where
                     }
                     terms_list::TermListOutput::SelectedTerm(selected_term) => {
                         if !self.tabs.select(&selected_term) {
-                            self.tabs.push(&self.terms.get(&selected_term).unwrap());
-                            self.tabs.select(&selected_term);
+                            match self.terms.get(&selected_term) {
+                                Ok(Some(term)) => {
+                                    self.error = None;
+                                    self.tabs.push(&term);
+                                    self.tabs.select(&selected_term);
+                                }
+                                Ok(None) => debug!("selected term {} is missing", selected_term),
+                                Err(e) => {
+                                    self.error =
+                                        Some(format!("Can't open {}: {}", selected_term, e))
+                                }
+                            }
                         }
                     }
                 }
@@ -75,11 +122,109 @@ where
             ui.separator();
             ui.vertical_centered_justified(|ui| {
                 if let Some(module_path) = self.load_menu.show(ui) {
-                    self.terms = T::load(&module_path);
+                    match T::load(&module_path) {
+                        Ok(terms) => {
+                            self.terms = terms;
+                            (self.history, self.error) = open_history(&module_path);
+                        }
+                        Err(e) => {
+                            self.error =
+                                Some(format!("Can't load {}: {}", module_path.display(), e))
+                        }
+                    }
+                }
+            });
+            ui.vertical_centered_justified(|ui| {
+                if ui.button("Check facts").clicked() {
+                    self.type_errors = Some(
+                        type_check::check_store(&self.terms)
+                            .iter()
+                            .map(|e| e.to_string())
+                            .collect(),
+                    );
+                }
+            });
+            match &self.type_errors {
+                Some(type_errors) if type_errors.is_empty() => {
+                    ui.label("All facts fit their arg types");
+                }
+                Some(type_errors) => {
+                    for type_error in type_errors {
+                        ui.colored_label(ui.visuals().warn_fg_color, type_error);
+                    }
+                }
+                None => {}
+            }
+            ui.vertical_centered_justified(|ui| {
+                if ui.button("Lint").clicked() {
+                    self.diagnostics = Some(lint::lint_store(&self.terms));
                 }
             });
+            match &self.diagnostics {
+                Some(diagnostics) if diagnostics.is_empty() => {
+                    ui.label("No lint findings");
+                }
+                Some(diagnostics) => {
+                    for diagnostic in diagnostics {
+                        let color = match diagnostic.severity() {
+                            Severity::Warning => ui.visuals().warn_fg_color,
+                            Severity::Error => ui.visuals().error_fg_color,
+                        };
+                        ui.colored_label(color, diagnostic.to_string());
+                    }
+                }
+                None => {}
+            }
+            ui.vertical_centered_justified(|ui| {
+                if ui.button("Check @see").clicked() {
+                    self.integrity = Some(integrity::check(&self.terms));
+                }
+            });
+            if let Some(report) = &self.integrity {
+                if report.is_consistent() {
+                    ui.label("All @see lists match the rules");
+                }
+                for discrepancy in &report.discrepancies {
+                    ui.colored_label(ui.visuals().warn_fg_color, discrepancy.to_string());
+                }
+                for (term_key, err) in &report.unreadable {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        format!("{}: can't be read: {}", term_key, err),
+                    );
+                }
+                if !report.is_consistent() {
+                    // open terms hold their own copy of the @see list
+                    let can_repair = !self.tabs.has_open_terms();
+                    let repair = ui
+                        .add_enabled(can_repair, egui::Button::new("Repair @see"))
+                        .on_disabled_hover_text("Close the open terms first");
+                    if repair.clicked() {
+                        match change_propagation::repair_references(&mut self.terms, report) {
+                            Ok(written) => {
+                                debug!("repaired the @see lists of {:?}", written.changed);
+                                if let Err(e) =
+                                    change_propagation::record(self.history.as_ref(), written)
+                                {
+                                    self.error = Some(e.to_string());
+                                }
+                            }
+                            Err(e) => self.error = Some(format!("Can't repair @see: {}", e)),
+                        }
+                        self.integrity = None;
+                    }
+                }
+            }
+            if let Some(error) = &self.error {
+                ui.separator();
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
         });
 
-        self.tabs.show(ctx, &mut self.terms)
+        if let Err(e) = self.tabs.show(ctx, &mut self.terms, self.history.as_ref()) {
+            self.error = Some(e.to_string());
+            // the side panel is already drawn for this frame
+            ctx.request_repaint();
+        }
     }
 }
@@ -1,7 +1,11 @@
+use its_logical::knowledge::history::History;
 use its_logical::knowledge::model::comment::name_description::NameDescription;
+use its_logical::knowledge::model::term::arg::Arg;
 use its_logical::knowledge::model::term::args_binding::ArgsBinding;
 use its_logical::knowledge::model::term::bound_term::BoundTerm;
-use its_logical::knowledge::store::Consult;
+use its_logical::knowledge::model::term::split_term_key;
+use its_logical::knowledge::store::past::PastTerms;
+use its_logical::knowledge::store::{self, Consult};
 use its_logical::knowledge::store::{Get, Keys};
 
 use crate::suggestions::FuzzySuggestions;
@@ -13,19 +17,38 @@ mod table;
 
 // TODO: move under term_tabs module
 pub(crate) struct Ask {
-    term_name: String,
+    // the "name/arity" key of the asked term
+    term_key: String,
     anchors: Vec<Option<String>>,
-    args_initial: Vec<NameDescription>,
+    // None until a term is chosen - zero-arity terms have Some(vec![])
+    args_initial: Option<Vec<NameDescription>>,
     results: Table,
+    // the chosen term is in the store, but can't be read or consulting it failed
+    error: Option<String>,
+    // a commit or a YYYY-MM-DD date of the module's history, as typed in
+    compare_with: String,
+    // the knowledge the answers are compared to - None when not comparing
+    past: Option<Past>,
+    past_results: Table,
+}
+
+// the knowledge as it was at a revision - read-only, it can only be consulted
+struct Past {
+    revision: String,
+    terms: PastTerms,
 }
 
 impl Ask {
     pub(crate) fn new() -> Self {
         Self {
-            term_name: String::new(),
+            term_key: String::new(),
             anchors: vec![],
-            args_initial: vec![],
+            args_initial: None,
             results: Table::new(),
+            error: None,
+            compare_with: String::new(),
+            past: None,
+            past_results: Table::new(),
         }
     }
 
@@ -35,11 +58,17 @@ impl Ask {
 }
 
 impl Ask {
-    pub(crate) fn show(&mut self, ui: &mut egui::Ui, terms: &mut (impl Get + Keys + Consult)) {
+    // with `history` the answers can be compared to the ones the knowledge gave at a past revision
+    pub(crate) fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        terms: &mut (impl Get + Keys + Consult),
+        history: Option<&History>,
+    ) {
         let term_suggestions = FuzzySuggestions::new(terms.keys().iter().cloned());
         if popup_suggestions::show(
             ui,
-            &mut self.term_name,
+            &mut self.term_key,
             |ui, current_val| {
                 ui.add(
                     egui::TextEdit::singleline(current_val)
@@ -53,22 +82,43 @@ impl Ask {
         )
         .changed()
         {
-            // TODO: handle the None here
-            let t = terms
-                .get(&self.term_name)
-                .expect("selections should only be made from the available terms");
-            self.args_initial = t.meta.args;
-            self.anchors = vec![None; self.args_initial.len()];
-            // reset any results from before
-            self.results = Table::new();
-            ui.label("Try to consult");
+            self.error = None;
+            match terms.get(&self.term_key) {
+                Ok(Some(t)) => {
+                    self.anchors = vec![None; t.meta.args.len()];
+                    self.args_initial = Some(t.meta.args);
+                    // reset any results from before
+                    self.results = Table::new();
+                    self.past_results = Table::new();
+                    ui.label("Try to consult");
+                }
+                // the name is still being typed
+                Ok(None) => {}
+                Err(e) => {
+                    self.args_initial = None;
+                    self.error = Some(e.to_string());
+                }
+            }
+        }
+        match history {
+            Some(history) => self.show_compare(ui, history),
+            // another module was loaded
+            None => self.past = None,
+        }
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
         }
         ui.separator();
 
-        if !self.args_initial.is_empty() {
+        if self.args_initial.is_some() {
             ui.horizontal(|ui| {
                 ui.vertical(|ui| {
-                    for (arg, anchored) in self.args_initial.iter().zip(self.anchors.iter_mut()) {
+                    for (arg, anchored) in self
+                        .args_initial
+                        .iter()
+                        .flatten()
+                        .zip(self.anchors.iter_mut())
+                    {
                         ui.horizontal(|ui| {
                             ui.label(&arg.name).on_hover_text(&arg.desc);
                             match anchored {
@@ -101,22 +151,42 @@ impl Ask {
                 });
                 ui.separator();
                 if self.results.show(ui) {
-                    let bound_term = build_bound_term(&self.term_name, &self.extract_anchors())
+                    let (term_name, _) =
+                        split_term_key(&self.term_key).expect("a term was chosen by its key");
+                    let bound_term = build_bound_term(term_name, &self.extract_anchors())
                         .expect("couldn't build bound term");
 
-                    let consult = terms.consult(&bound_term);
-                    let mut current_results = Vec::with_capacity(consult.len());
+                    let current_results = solutions(terms, &bound_term).unwrap_or_else(|e| {
+                        self.error = Some(e.to_string());
+                        vec![]
+                    });
+                    self.results.set_content(current_results);
 
-                    for binding in consult {
-                        let mut with_anchors = bound_term.arg_bindings.binding.clone();
-                        with_anchors.iter_mut().for_each(|x| {
-                            if let Some(bound_value) = binding.get(x) {
-                                *x = bound_value.to_owned()
-                            }
-                        });
-                        current_results.push(with_anchors);
+                    if let Some(past) = &mut self.past {
+                        let past_results = if past.terms.keys().contains(&self.term_key) {
+                            solutions(&mut past.terms, &bound_term).unwrap_or_else(|e| {
+                                self.error = Some(format!("At {}: {}", short(&past.revision), e));
+                                vec![]
+                            })
+                        } else {
+                            self.error = Some(format!(
+                                "{} wasn't there at {}",
+                                self.term_key,
+                                short(&past.revision)
+                            ));
+                            vec![]
+                        };
+                        self.past_results.set_content(past_results);
+                        self.results.mark_unmatched(&self.past_results);
+                        self.past_results.mark_unmatched(&self.results);
                     }
-                    self.results.set_content(current_results);
+                }
+                if let Some(past) = &self.past {
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        ui.label(format!("At {}", short(&past.revision)));
+                        self.past_results.show_read_only(ui, "past_results");
+                    });
                 }
             });
             ui.separator();
@@ -124,6 +194,79 @@ impl Ask {
     }
 }
 
+impl Ask {
+    fn show_compare(&mut self, ui: &mut egui::Ui, history: &History) {
+        ui.horizontal(|ui| {
+            ui.label("Compare with");
+            let edit = ui.add(
+                egui::TextEdit::singleline(&mut self.compare_with)
+                    .hint_text("revision or YYYY-MM-DD")
+                    .desired_width(130.0),
+            );
+            let submitted = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Open").clicked() || submitted {
+                self.past = None;
+                self.past_results = Table::new();
+                self.error = None;
+                let opened = history
+                    .find_revision(self.compare_with.trim())
+                    .and_then(|revision| {
+                        Ok(Past {
+                            terms: history.knowledge_at(&revision)?,
+                            revision,
+                        })
+                    });
+                match opened {
+                    Ok(past) => self.past = Some(past),
+                    Err(e) => self.error = Some(e.to_string()),
+                }
+            }
+            if let Some(past) = &self.past {
+                ui.label(format!("comparing to {}", short(&past.revision)));
+                if ui.button("❌").on_hover_text("stop comparing").clicked() {
+                    self.past = None;
+                    self.past_results = Table::new();
+                    self.results.clear_marks();
+                }
+            }
+        });
+    }
+}
+
+// the answers of the store, one per solution - the args of the term with the bindings in place
+fn solutions(
+    terms: &mut impl Consult,
+    bound_term: &BoundTerm,
+) -> Result<Vec<Vec<String>>, store::Error> {
+    let consult = terms.consult(bound_term)?;
+    let mut results = Vec::with_capacity(consult.len());
+    for binding in consult {
+        if bound_term.arg_bindings.binding.is_empty() {
+            // a zero-arity term has nothing to bind - a solution means it holds
+            results.push(vec!["true".to_string()]);
+            continue;
+        }
+        let with_anchors = bound_term
+            .arg_bindings
+            .binding
+            .iter()
+            .map(|x| match x {
+                Arg::Variable(var_name) => {
+                    binding.get(var_name).cloned().unwrap_or_else(|| x.encode())
+                }
+                _ => x.encode(),
+            })
+            .collect();
+        results.push(with_anchors);
+    }
+    Ok(results)
+}
+
+// enough of a commit id to tell it apart
+fn short(revision: &str) -> &str {
+    revision.get(..8).unwrap_or(revision)
+}
+
 fn get_next_random_var_name(current: &str) -> String {
     let (left, last_char) = current.split_at(current.len() - 1);
     let last_char = last_char
@@ -142,26 +285,25 @@ fn get_next_random_var_name(current: &str) -> String {
 }
 
 fn build_bound_term(term_name: &str, anchors: &[Option<String>]) -> Result<BoundTerm, String> {
-    let all_anchors_start_with_lower_case = anchors.iter().flatten().all(|x| {
-        x.chars()
-            .next()
-            .expect("anchors have at least 1 character")
-            .is_lowercase()
-    });
-    if !all_anchors_start_with_lower_case {
+    let anchors: Vec<Option<Arg>> = anchors
+        .iter()
+        .map(|x| x.as_ref().map(|anchor| Arg::from(anchor.as_str())))
+        .collect();
+
+    if anchors.iter().flatten().any(Arg::is_variable) {
         // TODO: maybe just filter out anchors that are upper-cased?
         return Err("there are anchors that would be interpreted as Prolog variables".to_string());
     }
 
     let mut current_var_name: String = ((b'A' - 1) as char).to_string();
-    let term_args: Vec<String> = anchors
-        .iter()
+    let term_args: Vec<Arg> = anchors
+        .into_iter()
         .map(|x| match x {
             None => {
                 current_var_name = get_next_random_var_name(&current_var_name);
-                current_var_name.clone()
+                Arg::Variable(current_var_name.clone())
             }
-            Some(anchor) => anchor.to_owned(),
+            Some(anchor) => anchor,
         })
         .collect();
 
@@ -2,16 +2,32 @@ use egui::RichText;
 
 pub(crate) struct Table {
     content: Vec<Vec<String>>,
+    // one per solution - set for the ones the table it's compared to doesn't have
+    unmatched: Vec<bool>,
 }
 
 impl Table {
     pub fn set_content(&mut self, content: Vec<Vec<String>>) {
         self.content = content;
+        self.clear_marks();
+    }
+
+    pub fn mark_unmatched(&mut self, other: &Table) {
+        self.unmatched = self
+            .content
+            .iter()
+            .map(|solution| !other.content.contains(solution))
+            .collect();
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.unmatched = vec![false; self.content.len()];
     }
 
     pub fn new() -> Self {
         Self {
             content: Vec::new(),
+            unmatched: Vec::new(),
         }
     }
 }
@@ -19,17 +35,32 @@ impl Table {
 impl Table {
     pub(crate) fn show(&mut self, ui: &mut egui::Ui) -> bool {
         egui::ScrollArea::horizontal()
+            .id_salt("results")
             .show(ui, |ui| {
-                for item in &self.content {
-                    ui.vertical(|ui| {
-                        for i in item {
-                            ui.add(egui::Label::new(RichText::new(i).monospace()));
-                        }
-                    });
-                }
+                self.show_solutions(ui);
                 ui.add(egui::Button::new(RichText::new("Consult").heading()))
                     .clicked()
             })
             .inner
     }
+
+    pub(crate) fn show_read_only(&self, ui: &mut egui::Ui, id_salt: &str) {
+        egui::ScrollArea::horizontal()
+            .id_salt(id_salt)
+            .show(ui, |ui| self.show_solutions(ui));
+    }
+
+    fn show_solutions(&self, ui: &mut egui::Ui) {
+        for (item, &unmatched) in self.content.iter().zip(&self.unmatched) {
+            ui.vertical(|ui| {
+                for i in item {
+                    let mut text = RichText::new(i).monospace();
+                    if unmatched {
+                        text = text.color(ui.visuals().warn_fg_color);
+                    }
+                    ui.add(egui::Label::new(text));
+                }
+            });
+        }
+    }
 }
@@ -1,6 +1,7 @@
 use its_logical::changes::{self, change};
+use its_logical::knowledge::history::History;
 use its_logical::knowledge::model::fat_term::FatTerm;
-use its_logical::knowledge::store::{Consult, Delete, Get, Keys, Put};
+use its_logical::knowledge::store::{Consult, Get, Keys, Transact};
 
 use crate::change_propagation;
 use crate::terms_cache::{TermHolder, TermsCache};
@@ -39,11 +40,15 @@ impl Default for Tabs {
 }
 
 impl Tabs {
+    // a store commit is all or nothing and the tabs follow it only once it succeeded, so a failed
+    // store operation leaves both as they were. Whatever the store took is committed to `history`
     pub(crate) fn show(
         &mut self,
         ctx: &egui::Context,
-        terms: &mut (impl Get + Put + Delete + Keys + Consult),
-    ) {
+        terms: &mut (impl Get + Transact + Keys + Consult),
+        history: Option<&History>,
+    ) -> Result<(), change_propagation::Error> {
+        let mut result = Ok(());
         egui::TopBottomPanel::top("tabs_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.selectable_value(
@@ -60,17 +65,24 @@ impl Tabs {
                             let selected_name = match self.current_selection {
                                 ChosenTab::TermScreen(idx) => {
                                     self.term_tabs.get_by_idx(idx).map(|x| match x {
-                                        TermHolder::Normal(t) => t.name(),
-                                        TermHolder::TwoPhase(t) => t.name(),
+                                        TermHolder::Normal(t) => t.key(),
+                                        TermHolder::TwoPhase(t) => t.key(),
                                     })
                                 }
                                 _ => None,
                             };
-                            let deleted =
-                                change_propagation::finish_commit(terms, &mut self.term_tabs);
+                            let written =
+                                match change_propagation::finish_commit(terms, &mut self.term_tabs)
+                                {
+                                    Ok(written) => written,
+                                    Err(e) => {
+                                        result = Err(e.into());
+                                        return;
+                                    }
+                                };
 
                             if let Some(selected_name) = selected_name {
-                                if deleted.contains(&selected_name) {
+                                if written.deleted.contains(&selected_name) {
                                     self.current_selection = ChosenTab::Ask;
                                 } else {
                                     // there is some chance that there have been deleted elements before the selected
@@ -78,6 +90,7 @@ impl Tabs {
                                     self.select(&selected_name);
                                 }
                             }
+                            result = change_propagation::record(history, written);
                         }
                         term_tabs::Output::AbortedCommit => {
                             change_propagation::revert_commit(&mut self.term_tabs)
@@ -89,7 +102,7 @@ impl Tabs {
 
         match self.current_selection {
             ChosenTab::Ask => {
-                egui::CentralPanel::default().show(ctx, |ui| self.ask.show(ui, terms));
+                egui::CentralPanel::default().show(ctx, |ui| self.ask.show(ui, terms, history));
             }
             ChosenTab::TermScreen(screen_idx) => {
                 if let Some(term_screen) = self.term_tabs.get_by_idx_mut(screen_idx) {
@@ -121,21 +134,29 @@ impl Tabs {
                                         changes,
                                     ))
                                     .as_slice(),
-                                    updated_term,
+                                    *updated_term,
                                 );
-                                change_propagation::propagate_change(
+                                result = change_propagation::propagate_change(
                                     &change,
                                     terms,
                                     &mut self.term_tabs,
-                                );
+                                )
+                                .and_then(|written| change_propagation::record(history, written));
                             }
                             term_screen::Output::Deleted => {
-                                if change_propagation::propagate_deletion(
+                                match change_propagation::propagate_deletion(
                                     &original_term,
                                     terms,
                                     &mut self.term_tabs,
                                 ) {
-                                    self.current_selection = ChosenTab::Ask;
+                                    Ok(written) => {
+                                        // the term is gone right away only without confirmation
+                                        if !written.deleted.is_empty() {
+                                            self.current_selection = ChosenTab::Ask;
+                                        }
+                                        result = change_propagation::record(history, written);
+                                    }
+                                    Err(e) => result = Err(e),
                                 }
                             }
                         }
@@ -143,25 +164,30 @@ impl Tabs {
                 }
             }
         }
+        result
     }
 
-    pub(crate) fn select(&mut self, term_name: &str) -> bool {
-        if let Some(screen_idx) = self.term_tabs.find(term_name) {
+    pub(crate) fn select(&mut self, term_key: &str) -> bool {
+        if let Some(screen_idx) = self.term_tabs.find(term_key) {
             self.current_selection = ChosenTab::TermScreen(screen_idx);
             return true;
         }
         false
     }
 
+    pub(crate) fn has_open_terms(&self) -> bool {
+        self.term_tabs.iter().len() > 0
+    }
+
     pub(crate) fn push(&mut self, term: &FatTerm) {
         self.term_tabs.push(term);
     }
 
     pub(crate) fn get_mut(
         &mut self,
-        term_name: &str,
+        term_key: &str,
     ) -> Option<&mut TermHolder<TermScreen, TwoPhaseCommitScreen>> {
-        self.term_tabs.get_mut(term_name)
+        self.term_tabs.get_mut(term_key)
     }
 }
 
@@ -1,5 +1,6 @@
 use std::cmp::min;
 
+use its_logical::knowledge::model::term::split_term_key;
 use screen::Screen;
 
 use crate::{
@@ -29,28 +30,29 @@ impl TermsCache<TermScreen, TwoPhaseCommitScreen> {
         ui.horizontal(|ui| {
             let mut close_idx = None;
             for (idx, screen) in self.iter_mut().enumerate() {
-                let (name, stroke, can_close) = match screen {
+                let (key, stroke, can_close) = match screen {
                     crate::terms_cache::TermHolder::Normal(s) => {
-                        (s.name(), s.stroke(), s.can_close())
+                        (s.key(), s.stroke(), s.can_close())
                     }
                     crate::terms_cache::TermHolder::TwoPhase(s) => {
                         has_two_phase_commit = true;
                         is_ready_for_commit =
                             is_ready_for_commit && !s.two_phase_commit().borrow().is_waiting();
-                        (s.name(), s.stroke(), s.can_close())
+                        (s.key(), s.stroke(), s.can_close())
                     }
                 };
+                let is_untitled = matches!(split_term_key(&key), Some(("", _)));
 
                 ui.scope(|ui| {
                     let selectable = ui.selectable_value(
                         current_tab,
                         ChosenTab::TermScreen(idx),
-                        if name.is_empty() {
+                        if is_untitled {
                             "untitled*".to_string()
                         } else if !can_close {
-                            name + "*"
+                            key + "*"
                         } else {
-                            name
+                            key
                         },
                     );
 
@@ -69,9 +71,9 @@ impl TermsCache<TermScreen, TwoPhaseCommitScreen> {
             }
             if let Some(close_idx) = close_idx {
                 if let Some(to_be_closed) = self.get_by_idx(close_idx) {
-                    let (can_close, name) = match to_be_closed {
-                        crate::terms_cache::TermHolder::Normal(s) => (s.can_close(), s.name()),
-                        crate::terms_cache::TermHolder::TwoPhase(s) => (s.can_close(), s.name()),
+                    let (can_close, key) = match to_be_closed {
+                        crate::terms_cache::TermHolder::Normal(s) => (s.can_close(), s.key()),
+                        crate::terms_cache::TermHolder::TwoPhase(s) => (s.can_close(), s.key()),
                     };
 
                     if !can_close {
@@ -89,7 +91,7 @@ impl TermsCache<TermScreen, TwoPhaseCommitScreen> {
                             }
                         }
 
-                        self.remove(&name);
+                        self.remove(&key);
                     }
                 }
             }
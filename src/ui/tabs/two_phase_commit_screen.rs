@@ -22,8 +22,8 @@ impl crate::terms_cache::NamedTerm for TwoPhaseCommitScreen {
         Self::new(TermScreen::new(term, false))
     }
 
-    fn name(&self) -> String {
-        self.name()
+    fn key(&self) -> String {
+        self.key()
     }
 
     fn term(&self) -> FatTerm {
@@ -88,8 +88,8 @@ impl TwoPhaseCommitScreen {
         }
     }
 
-    pub(crate) fn name(&self) -> String {
-        self.screen.name()
+    pub(crate) fn key(&self) -> String {
+        self.screen.key()
     }
 
     pub(crate) fn extract_term(&self) -> FatTerm {
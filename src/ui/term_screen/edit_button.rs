@@ -1,8 +1,13 @@
 use egui::Ui;
 
-pub(crate) fn show_edit_button(ui: &mut Ui, in_edit: bool) -> bool {
+// saving is refused while `can_save` is false - the edit can still go on
+pub(crate) fn show_edit_button(ui: &mut Ui, in_edit: bool, can_save: bool) -> bool {
     let toggle_value_text = if in_edit { "💾" } else { "🖊" };
 
-    ui.button(egui::RichText::new(toggle_value_text).heading())
-        .clicked()
+    ui.add_enabled(
+        !in_edit || can_save,
+        egui::Button::new(egui::RichText::new(toggle_value_text).heading()),
+    )
+    .on_disabled_hover_text("An enum needs a value and a ref needs a name/arity")
+    .clicked()
 }
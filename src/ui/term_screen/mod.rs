@@ -6,7 +6,7 @@ use self::points_in_time::PointsInTime;
 use self::term_screen_pit::{TermChange, TermScreenPIT};
 
 pub(crate) enum Output {
-    Changes(Vec<TermChange>, FatTerm),
+    Changes(Vec<TermChange>, Box<FatTerm>),
     Deleted,
 }
 
@@ -30,8 +30,8 @@ impl crate::terms_cache::NamedTerm for TermScreen {
         Self::new(term, false)
     }
 
-    fn name(&self) -> String {
-        self.name()
+    fn key(&self) -> String {
+        self.key()
     }
 
     fn term(&self) -> FatTerm {
@@ -110,8 +110,8 @@ impl TermScreen {
             .start_changes();
     }
 
-    pub(crate) fn name(&self) -> String {
-        self.points_in_time.original().name()
+    pub(crate) fn key(&self) -> String {
+        self.points_in_time.original().key()
     }
 
     pub(crate) fn in_edit(&self) -> bool {
@@ -141,19 +141,19 @@ impl TermScreen {
         if !self.in_deletion {
             match &mut self.current {
                 Some(current) => {
-                    if edit_button::show_edit_button(ui, true) {
+                    if edit_button::show_edit_button(ui, true, current.can_finish_changes()) {
                         // one last frame of the term not being editable with the newest state
                         current.show(ui, terms_knowledge_base, false);
                         let changes = current.finish_changes();
                         self.current = None;
                         self.showing_point_in_time = Some(self.points_in_time.len() - 1);
                         if let Some((changes, updated_term)) = changes {
-                            return Some(Output::Changes(changes, updated_term));
+                            return Some(Output::Changes(changes, Box::new(updated_term)));
                         }
                     }
                 }
                 None => {
-                    if edit_button::show_edit_button(ui, false) {
+                    if edit_button::show_edit_button(ui, false, true) {
                         self.start_changes();
                     }
                 }
@@ -3,9 +3,12 @@ use egui::RichText;
 use its_logical::knowledge::{
     model::{
         comment::name_description::NameDescription,
-        term::{args_binding::ArgsBinding, bound_term::BoundTerm, rule::Rule},
+        term::{
+            arg::Arg, args_binding::ArgsBinding, bound_term::BoundTerm, goal::Goal, rule::Rule,
+        },
     },
     store::{Get, Keys},
+    type_check,
 };
 
 use crate::ui::widgets::{drag_and_drop::DragAndDrop, popup_suggestions};
@@ -27,12 +30,17 @@ impl HeadPlaceholder {
         term_name: &str,
         template: impl ExactSizeIterator<Item = &'a NameDescription>,
     ) {
-        ui.label(egui::RichText::new(format!("{} (", term_name)).weak());
-
-        let mut added_once = false;
         if template.len() != self.binding.len() {
             self.binding = vec![String::new(); template.len()];
         }
+        if self.binding.is_empty() {
+            // zero-arity terms have no parentheses
+            ui.label(egui::RichText::new(term_name).weak());
+            return;
+        }
+        ui.label(egui::RichText::new(format!("{} (", term_name)).weak());
+
+        let mut added_once = false;
 
         for (template_param, param) in template.zip(self.binding.iter_mut()) {
             if added_once {
@@ -52,49 +60,74 @@ impl HeadPlaceholder {
 
 pub(crate) struct FactPlaceholder {
     head: HeadPlaceholder,
+    // why the last attempt to finish the fact was refused
+    type_errors: Vec<String>,
 }
 
 impl FactPlaceholder {
     pub(crate) fn new(args: &[String]) -> Self {
         Self {
             head: HeadPlaceholder::new(args),
+            type_errors: vec![],
         }
     }
     pub(crate) fn show<'a>(
         &mut self,
         ui: &mut egui::Ui,
         term_name: &str,
+        terms_knowledge_base: &impl Get,
         template: impl ExactSizeIterator<Item = &'a NameDescription>,
         finish_button_text: &str,
     ) -> Option<ArgsBinding> {
-        self.head.show(ui, term_name, template);
+        let template: Vec<NameDescription> = template.cloned().collect();
+        self.head.show(ui, term_name, template.iter());
         if ui
             .small_button(RichText::new(finish_button_text).monospace())
             .clicked()
         {
-            let mut empty_fact_placeholder = FactPlaceholder::new(&[]);
-            // reset the placeholder
-            std::mem::swap(&mut empty_fact_placeholder, self);
-
-            return Some(ArgsBinding {
-                binding: empty_fact_placeholder.head.binding,
-            });
+            // the fact is kept in the placeholder until it fits the declared arg types
+            let fact = self.head.fact();
+            self.type_errors = type_check::check_fact(&template, &fact, terms_knowledge_base)
+                .iter()
+                .map(|e| e.to_string())
+                .collect();
+            if self.type_errors.is_empty() {
+                *self = FactPlaceholder::new(&[]);
+                return Some(fact);
+            }
+        }
+        for type_error in &self.type_errors {
+            ui.colored_label(ui.visuals().error_fg_color, type_error);
         }
         None
     }
 }
 
+// Plain calls in a rule body are editable; negations, disjunctions and if-then-elses are only
+// shown (and kept as they are) since the placeholder has no widgets for building them
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum BodyGoal {
+    Term(String, Vec<String>),
+    Other(Goal),
+}
+
+impl BodyGoal {
+    fn empty() -> Self {
+        BodyGoal::Term("".to_string(), vec![])
+    }
+}
+
 pub(crate) struct RulePlaceholder {
     head: HeadPlaceholder,
-    body: DragAndDrop<(String, Vec<String>)>,
+    body: DragAndDrop<BodyGoal>,
 }
 
 impl RulePlaceholder {
     pub(crate) fn new() -> Self {
         Self {
             head: HeadPlaceholder::new(&[]),
-            body: DragAndDrop::new(vec![("".to_string(), vec![])])
-                .with_create_item("constructor", Box::new(|| ("".to_string(), vec![]))),
+            body: DragAndDrop::new(vec![BodyGoal::empty()])
+                .with_create_item("constructor", Box::new(BodyGoal::empty)),
         }
     }
     pub(crate) fn show<'a>(
@@ -114,17 +147,27 @@ impl RulePlaceholder {
         let arg_suggestions = FuzzySuggestions::new(
             self.body
                 .iter()
-                .flat_map(|(_, vecs)| vecs)
+                .flat_map(|body_goal| match body_goal {
+                    BodyGoal::Term(_, args) => args.as_slice(),
+                    BodyGoal::Other(_) => &[],
+                })
                 .filter(|&x| !x.is_empty())
                 .chain(self.head.binding.iter())
                 .cloned(),
         );
 
         self.body.show(ui, |s, ui| {
+            let (name, args) = match s {
+                BodyGoal::Term(name, args) => (name, args),
+                BodyGoal::Other(goal) => {
+                    ui.label(goal.encode());
+                    return;
+                }
+            };
             ui.horizontal(|ui| {
                 if popup_suggestions::show(
                     ui,
-                    &mut s.0,
+                    name,
                     |ui, current_val| {
                         ui.add(
                             egui::TextEdit::singleline(current_val)
@@ -136,15 +179,22 @@ impl RulePlaceholder {
                 )
                 .changed()
                 {
-                    // TODO: handle the None here
-                    let t = terms_knowledge_base.get(&s.0).unwrap();
-                    s.1 = vec!["".to_string(); t.meta.args.len()];
-                    term_added_to_body = Some(t.meta.term.name);
+                    // the key might be only partially typed or the term might not be readable -
+                    // either way there are no args to suggest
+                    if let Ok(Some(t)) = terms_knowledge_base.get(name) {
+                        *args = vec!["".to_string(); t.meta.args.len()];
+                        // the key was picked - the arity now shows in the args
+                        *name = t.meta.term.name.clone();
+                        term_added_to_body = Some(t.key());
+                    }
+                }
+                if args.is_empty() {
+                    return;
                 }
                 let mut added_once = false;
 
                 ui.label(egui::RichText::new("(").weak());
-                for param in &mut s.1 {
+                for param in args.iter_mut() {
                     if added_once {
                         ui.label(egui::RichText::new(", ").weak());
                     }
@@ -194,25 +244,29 @@ impl From<RulePlaceholder> for Rule {
         let body_bindings = placeholder
             .body
             .iter()
-            .filter_map(|(name, args)| {
+            .filter_map(|body_goal| {
+                let (name, args) = match body_goal {
+                    BodyGoal::Term(name, args) => (name, args),
+                    BodyGoal::Other(goal) => return Some(goal.to_owned()),
+                };
                 // TODO: maybe do the check that name is not existing here
                 if name.is_empty() {
                     return None;
                 }
 
-                Some(BoundTerm {
+                // rule args are read the way Prolog reads them - "X" is a variable and constants
+                // come back quoted from encode_for_edit
+                Some(Goal::Term(BoundTerm {
                     name: name.to_owned(),
-                    arg_bindings: its_logical::knowledge::model::term::args_binding::ArgsBinding {
-                        binding: args.to_owned(),
+                    arg_bindings: ArgsBinding {
+                        binding: args.iter().map(|arg| Arg::from(arg.as_str())).collect(),
                     },
-                })
+                }))
             })
             .collect();
 
         Rule {
-            head: its_logical::knowledge::model::term::args_binding::ArgsBinding {
-                binding: head_binding.binding,
-            },
+            head: head_binding.into(),
             body: body_bindings,
         }
     }
@@ -222,22 +276,72 @@ impl From<Rule> for RulePlaceholder {
         let body_bindings = DragAndDrop::new(
             rule.body
                 .into_iter()
-                .map(|bound_term| {
-                    let BoundTerm { name, arg_bindings } = bound_term;
-                    (name, arg_bindings.binding)
+                .map(|goal| match goal {
+                    Goal::Term(BoundTerm { name, arg_bindings }) => {
+                        BodyGoal::Term(name, encode_for_edit(&arg_bindings))
+                    }
+                    goal => BodyGoal::Other(goal),
                 })
                 .collect(),
         )
-        .with_create_item("from rule", Box::new(|| ("".to_string(), vec![])));
+        .with_create_item("from rule", Box::new(BodyGoal::empty));
 
         RulePlaceholder {
             head: HeadPlaceholder {
-                binding: rule.head.binding,
+                binding: encode_for_edit(&rule.head),
             },
             body: body_bindings,
         }
     }
 }
 
+impl HeadPlaceholder {
+    // unlike a rule head, a fact has no variables to bind - "Bob" is the constant 'Bob'
+    fn fact(&self) -> ArgsBinding {
+        ArgsBinding {
+            binding: self.binding.iter().map(|arg| Arg::constant(arg)).collect(),
+        }
+    }
+}
+
+impl From<HeadPlaceholder> for ArgsBinding {
+    fn from(head: HeadPlaceholder) -> Self {
+        ArgsBinding {
+            binding: head
+                .binding
+                .iter()
+                .map(|arg| Arg::from(arg.as_str()))
+                .collect(),
+        }
+    }
+}
+
+// the placeholders edit each argument as plain text
+pub(crate) fn encode_for_edit(args: &ArgsBinding) -> Vec<String> {
+    args.binding.iter().map(|arg| arg.encode()).collect()
+}
+
 // TODO: get this from the framework if possible
 const SINGLE_CHAR_WIDTH: f32 = 11.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fact_args_are_constants() {
+        let head =
+            HeadPlaceholder::new(&["Bob".to_string(), "New York".to_string(), "_".to_string()]);
+        let fact = head.fact();
+        assert_eq!(
+            fact.binding,
+            vec![
+                Arg::Atom("Bob".to_string()),
+                Arg::Atom("New York".to_string()),
+                Arg::Variable("_".to_string()),
+            ]
+        );
+        // editing the fact again starts from what it encodes to
+        assert_eq!(HeadPlaceholder::new(&encode_for_edit(&fact)).fact(), fact);
+    }
+}
@@ -38,7 +38,7 @@ impl PointsInTime {
     }
 
     pub(crate) fn push_pit(&mut self, args_changes: &[ArgsChange], term: &FatTerm, source: &str) {
-        if source == self.points_in_time.last().unwrap_or(&self.original).name() {
+        if source == self.points_in_time.last().unwrap_or(&self.original).key() {
             self.pit_info
                 .push((ChangeSource::Internal, args_changes.to_vec()));
         } else {
@@ -3,9 +3,14 @@ use its_logical::knowledge::store::{Get, Keys};
 use its_logical::{
     changes::change,
     knowledge::model::{
-        comment::{name_description::NameDescription, Comment},
+        comment::{
+            arg_type::ArgType,
+            doc_tag::{self, DocTag},
+            name_description::NameDescription,
+            Comment,
+        },
         fat_term::FatTerm,
-        term::{args_binding::ArgsBinding, rule::Rule},
+        term::{arg::Arg, args_binding::ArgsBinding, goal::Goal, rule::Rule, term_key},
     },
 };
 use tracing::debug;
@@ -29,6 +34,8 @@ struct Term {
     rules: DragAndDrop<Rule>,
     facts: DragAndDrop<ArgsBinding>,
     arguments: DragAndDrop<NameDescription>,
+    // not editable in the app, only carried along
+    doc_tags: Vec<DocTag>,
     related: Vec<String>,
 }
 
@@ -45,8 +52,8 @@ pub(crate) struct TermScreenPIT {
 }
 
 impl TermScreenPIT {
-    pub(crate) fn name(&self) -> String {
-        self.term.meta.name.clone()
+    pub(crate) fn key(&self) -> String {
+        term_key(&self.term.meta.name, self.term.arguments.iter().len())
     }
 
     pub(crate) fn set_name(&mut self, new_name: &str) {
@@ -82,6 +89,14 @@ impl TermScreenPIT {
         self.term.facts.unlock();
     }
 
+    // an incomplete argument type would be written as something that can't be read back
+    pub(crate) fn can_finish_changes(&self) -> bool {
+        self.term
+            .arguments
+            .iter()
+            .all(|arg| arg.arg_type.as_ref().is_none_or(ArgType::is_complete))
+    }
+
     pub(crate) fn finish_changes(&mut self) -> Option<(Vec<TermChange>, FatTerm)> {
         let mut result = None;
         let mut changes = vec![];
@@ -156,20 +171,18 @@ impl TermScreenPIT {
             );
 
             ui.vertical(|ui| {
+                let mut arg_idx = 0;
                 let mut args_change = self.term.arguments.show(ui, |s, ui| {
                     ui.horizontal(|ui| {
-                        self.arg_rename |= Self::show_arg(ui, &mut s.name, &mut s.desc, edit_mode);
+                        // a type change is no different than a rename for the rest of the knowledge
+                        self.arg_rename |= Self::show_arg(ui, s, arg_idx, edit_mode);
                     });
+                    arg_idx += 1;
                 });
 
                 if edit_mode {
                     ui.horizontal(|ui| {
-                        Self::show_arg(
-                            ui,
-                            &mut self.arg_placeholder.name,
-                            &mut self.arg_placeholder.desc,
-                            edit_mode,
-                        );
+                        Self::show_arg(ui, &mut self.arg_placeholder, usize::MAX, edit_mode);
                         if ui.small_button("➕").clicked() {
                             let mut empty_arg_placeholder = NameDescription::new("", "");
                             // reset the arg placeholder
@@ -212,6 +225,14 @@ impl TermScreenPIT {
                                     .font(TextStyle::Body),
                             )
                             .changed();
+                        for doc_tag in &self.term.doc_tags {
+                            let doc_tag_label = RichText::new(doc_tag.encode()).small();
+                            if doc_tag.name == doc_tag::DEPRECATED {
+                                ui.colored_label(ui.visuals().warn_fg_color, doc_tag_label);
+                            } else {
+                                ui.label(doc_tag_label.weak());
+                            }
+                        }
                     },
                 );
             });
@@ -220,7 +241,7 @@ impl TermScreenPIT {
         self.show_rules_section(ui, edit_mode, terms_knowledge_base);
         ui.separator();
 
-        self.show_facts_section(ui, edit_mode);
+        self.show_facts_section(ui, edit_mode, terms_knowledge_base);
         ui.separator();
 
         egui::ScrollArea::vertical()
@@ -241,7 +262,12 @@ impl TermScreenPIT {
         if edit_mode {}
     }
 
-    fn show_facts_section(&mut self, ui: &mut egui::Ui, edit_mode: bool) {
+    fn show_facts_section(
+        &mut self,
+        ui: &mut egui::Ui,
+        edit_mode: bool,
+        terms_knowledge_base: &impl Get,
+    ) {
         egui::ScrollArea::vertical()
             .id_source("facts_scroll_area")
             .show(ui, |ui| {
@@ -252,8 +278,7 @@ impl TermScreenPIT {
                         let mut idx = 0;
                         let mut edited_fact = None;
                         self.term.facts.show(ui, |f, ui| {
-                            let arguments_string: String = f.binding.join(", ");
-                            ui.label(format!("{} ( {} )", &self.term.meta.name, arguments_string));
+                            ui.label(format_bound_term(&self.term.meta.name, f));
 
                             if edit_mode
                                 && self.fact_editing.is_none()
@@ -267,8 +292,9 @@ impl TermScreenPIT {
                         if edit_mode {
                             if let Some(edited_fact_idx) = edited_fact {
                                 let fact_for_edit = self.term.facts.remove(edited_fact_idx);
-                                self.fact_editing =
-                                    Some(placeholder::FactPlaceholder::new(&fact_for_edit.binding));
+                                self.fact_editing = Some(placeholder::FactPlaceholder::new(
+                                    &placeholder::encode_for_edit(&fact_for_edit),
+                                ));
                             }
                             let mut finished_fact_editing = false;
                             if let Some(fact_editing) = &mut self.fact_editing {
@@ -276,6 +302,7 @@ impl TermScreenPIT {
                                     if let Some(edited_fact) = fact_editing.show(
                                         ui,
                                         &self.term.meta.name,
+                                        terms_knowledge_base,
                                         self.term.arguments.iter(),
                                         "✔",
                                     ) {
@@ -291,6 +318,7 @@ impl TermScreenPIT {
                                 if let Some(new_fact_binding) = self.fact_placeholder.show(
                                     ui,
                                     &self.term.meta.name,
+                                    terms_knowledge_base,
                                     self.term.arguments.iter(),
                                     "Add fact",
                                 ) {
@@ -319,23 +347,12 @@ impl TermScreenPIT {
                         let mut idx = 0;
                         let mut edited_rule = None;
                         self.term.rules.show(ui, |r, ui| {
-                            let arguments_string: String = r.head.binding.join(", ");
-
-                            let body_strings: Vec<String> = r
-                                .body
-                                .iter()
-                                .map(|c| {
-                                    let arguments_string: String =
-                                        c.arg_bindings.binding.join(", ");
-
-                                    format!("{} ( {} )", c.name, arguments_string)
-                                })
-                                .collect();
+                            let body_strings: Vec<String> =
+                                r.body.iter().map(format_goal).collect();
 
                             ui.label(format!(
-                                "{} ( {} ) if {}",
-                                &self.term.meta.name,
-                                arguments_string,
+                                "{} if {}",
+                                format_bound_term(&self.term.meta.name, &r.head),
                                 body_strings.join(", ")
                             ));
 
@@ -391,17 +408,18 @@ impl TermScreenPIT {
             });
     }
 
+    // `arg_idx` tells apart the type pickers of the different args
     fn show_arg(
         ui: &mut egui::Ui,
-        arg_name: &mut String,
-        arg_desc: &mut String,
+        arg: &mut NameDescription,
+        arg_idx: usize,
         edit_mode: bool,
     ) -> bool {
         // TODO: fix the hardcoded widths
         let mut changed = false;
         changed |= ui
             .add(
-                egui::TextEdit::singleline(arg_name)
+                egui::TextEdit::singleline(&mut arg.name)
                     .clip_text(false)
                     .hint_text("Name")
                     .desired_width(60.0)
@@ -411,10 +429,18 @@ impl TermScreenPIT {
             )
             .changed();
 
+        if edit_mode {
+            changed |= Self::show_arg_type(ui, &mut arg.arg_type, arg_idx);
+        } else if let Some(arg_type) = &arg.arg_type {
+            ui.label(RichText::new(format!(":{}", arg_type)).small().weak());
+        }
+
         changed |= ui
             .add(
-                egui::TextEdit::singleline(arg_desc)
+                // descriptions may span several lines
+                egui::TextEdit::multiline(&mut arg.desc)
                     .clip_text(false)
+                    .desired_rows(1)
                     .hint_text("Description")
                     .desired_width(100.0)
                     .frame(edit_mode)
@@ -424,6 +450,68 @@ impl TermScreenPIT {
             .changed();
         changed
     }
+
+    fn show_arg_type(ui: &mut egui::Ui, arg_type: &mut Option<ArgType>, arg_idx: usize) -> bool {
+        let mut changed = false;
+        let selected = arg_type.as_ref().map_or("any", arg_type_label);
+        egui::ComboBox::from_id_salt(("arg_type", arg_idx))
+            .selected_text(RichText::new(selected).small())
+            .width(60.0)
+            .show_ui(ui, |ui| {
+                let options = [
+                    None,
+                    Some(ArgType::Atom),
+                    Some(ArgType::Integer),
+                    Some(ArgType::Float),
+                    Some(ArgType::Date),
+                    Some(ArgType::Enum(vec![])),
+                    Some(ArgType::Reference(String::new())),
+                ];
+                for option in options {
+                    let label = option.as_ref().map_or("any", arg_type_label);
+                    let is_selected = arg_type.as_ref().map(arg_type_label)
+                        == option.as_ref().map(arg_type_label);
+                    if ui.selectable_label(is_selected, label).clicked() && !is_selected {
+                        *arg_type = option;
+                        changed = true;
+                    }
+                }
+            });
+
+        match arg_type {
+            Some(ArgType::Enum(values)) => {
+                let mut joined = values.join("|");
+                if ui
+                    .add(
+                        egui::TextEdit::singleline(&mut joined)
+                            .hint_text("a|b|c")
+                            .desired_width(80.0)
+                            .font(TextStyle::Small),
+                    )
+                    .changed()
+                {
+                    *values = joined
+                        .split('|')
+                        .map(|v| v.trim().to_owned())
+                        .filter(|v| !v.is_empty())
+                        .collect();
+                    changed = true;
+                }
+            }
+            Some(ArgType::Reference(term_key)) => {
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(term_key)
+                            .hint_text("name/arity")
+                            .desired_width(80.0)
+                            .font(TextStyle::Small),
+                    )
+                    .changed();
+            }
+            _ => {}
+        }
+        changed
+    }
 }
 
 impl Term {
@@ -432,6 +520,7 @@ impl Term {
         rules: DragAndDrop<Rule>,
         facts: DragAndDrop<ArgsBinding>,
         arguments: DragAndDrop<NameDescription>,
+        doc_tags: Vec<DocTag>,
         related: Vec<String>,
     ) -> Self {
         Self {
@@ -439,6 +528,7 @@ impl Term {
             rules,
             facts,
             arguments,
+            doc_tags,
             related,
         }
     }
@@ -451,6 +541,7 @@ impl From<&FatTerm> for Term {
             DragAndDrop::new(fat_term.term.rules.to_owned()),
             DragAndDrop::new(fat_term.term.facts.to_owned()),
             DragAndDrop::new(fat_term.meta.args.to_owned()),
+            fat_term.meta.doc_tags.to_owned(),
             fat_term.meta.referred_by.to_owned(),
         )
     }
@@ -458,12 +549,14 @@ impl From<&FatTerm> for Term {
 
 impl From<&Term> for FatTerm {
     fn from(term: &Term) -> Self {
+        let mut comment = Comment::new(
+            term.meta.to_owned(),
+            term.arguments.iter().as_slice(),
+            term.related.as_slice(),
+        );
+        comment.doc_tags = term.doc_tags.clone();
         Self::new(
-            Comment::new(
-                term.meta.to_owned(),
-                term.arguments.iter().as_slice(),
-                term.related.as_slice(),
-            ),
+            comment,
             its_logical::knowledge::model::term::Term::new(
                 term.facts.iter().as_slice(),
                 term.rules.iter().as_slice(),
@@ -472,6 +565,46 @@ impl From<&Term> for FatTerm {
     }
 }
 
+fn format_bound_term(name: &str, args: &ArgsBinding) -> String {
+    if args.binding.is_empty() {
+        return name.to_owned();
+    }
+    format!(
+        "{} ( {} )",
+        name,
+        placeholder::encode_for_edit(args).join(", ")
+    )
+}
+
+fn format_goal(goal: &Goal) -> String {
+    match goal {
+        Goal::Term(bound_term) => format_bound_term(&bound_term.name, &bound_term.arg_bindings),
+        Goal::BuiltIn(built_in) => built_in.encode(),
+        Goal::Not(goal) => format!("not {}", format_goal(goal)),
+        Goal::Conjunction(goals) => format_goals(goals, ", "),
+        Goal::Disjunction(goals) => format!("( {} )", format_goals(goals, " or ")),
+        Goal::IfThenElse {
+            condition,
+            then,
+            otherwise,
+        } => {
+            let mut formatted =
+                format!("( if {} then {}", format_goal(condition), format_goal(then));
+            if let Some(otherwise) = otherwise {
+                formatted.push_str(" else ");
+                formatted.push_str(&format_goal(otherwise));
+            }
+            formatted.push_str(" )");
+            formatted
+        }
+    }
+}
+
+fn format_goals(goals: &[Goal], separator: &str) -> String {
+    let formatted: Vec<String> = goals.iter().map(format_goal).collect();
+    formatted.join(separator)
+}
+
 fn apply_head_args_change<'a>(
     rules: impl Iterator<Item = &'a mut Rule>,
     facts: impl Iterator<Item = &'a mut ArgsBinding>,
@@ -480,10 +613,10 @@ fn apply_head_args_change<'a>(
     for rule in rules {
         let removed_arg = change.apply(&mut rule.head);
         if let Some(removed_arg) = removed_arg {
-            for body_term in &mut rule.body {
+            for body_term in rule.body_terms_mut() {
                 for bound_arg in &mut body_term.arg_bindings.binding {
                     if bound_arg == &removed_arg {
-                        *bound_arg = "_".to_string();
+                        *bound_arg = Arg::Variable("_".to_string());
                     }
                 }
             }
@@ -504,3 +637,42 @@ impl From<&drag_and_drop::Change<NameDescription>> for change::ArgsChange {
         }
     }
 }
+
+fn arg_type_label(arg_type: &ArgType) -> &'static str {
+    match arg_type {
+        ArgType::Atom => "atom",
+        ArgType::Integer => "integer",
+        ArgType::Float => "float",
+        ArgType::Date => "date",
+        ArgType::Enum(_) => "enum",
+        ArgType::Reference(_) => "ref",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incomplete_arg_type_is_not_saved() {
+        let mut fat_term = FatTerm::default();
+        fat_term.meta.term.name = "likes".to_string();
+        fat_term.meta.args = vec![NameDescription::new("Color", "")];
+        let mut pit = TermScreenPIT::new(&fat_term);
+        pit.start_changes();
+        assert!(pit.can_finish_changes());
+
+        for incomplete in [
+            ArgType::Enum(vec![]),
+            ArgType::Reference(String::new()),
+            ArgType::Reference("person".to_string()),
+        ] {
+            pit.term.arguments.iter_mut().next().unwrap().arg_type = Some(incomplete);
+            assert!(!pit.can_finish_changes());
+        }
+
+        pit.term.arguments.iter_mut().next().unwrap().arg_type =
+            Some(ArgType::Reference("person/1".to_string()));
+        assert!(pit.can_finish_changes());
+    }
+}